@@ -0,0 +1,155 @@
+//! Generate the typed register and field enums of `reginfo` from the register
+//! description, so that `registers.toml` stays the only place a layout is given
+
+use std::collections::BTreeSet;
+use std::fmt::Write;
+use std::path::Path;
+
+const DESCRIPTION: &str = "src/reginfo/registers.toml";
+
+/// `port_control0` to `PortControl0`
+fn camel(name: &str) -> String {
+    name.split('_')
+        .map(|part| {
+            let mut chars = part.chars();
+            match chars.next() {
+                Some(first) => first.to_ascii_uppercase().to_string() + chars.as_str(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+/// `msb:lsb` or a single bit of field `reg.field` to (len, shift)
+fn bits(reg: &str, field: &str, bits: &str) -> (u8, u8) {
+    let bit = |text: &str| -> u8 {
+        match text.trim().parse() {
+            Ok(bit) if bit < 16 => bit,
+            _ => panic!("{}.{}: bits {:?} is no msb:lsb of 15:0", reg, field, bits),
+        }
+    };
+    let (msb, lsb) = match bits.split_once(':') {
+        Some((msb, lsb)) => (bit(msb), bit(lsb)),
+        None => (bit(bits), bit(bits)),
+    };
+    if msb < lsb {
+        panic!("{}.{}: bits {:?} has msb below lsb", reg, field, bits);
+    }
+    (msb - lsb + 1, lsb)
+}
+
+fn str_of<'a>(table: &'a toml::Table, key: &str) -> &'a str {
+    table[key].as_str().unwrap()
+}
+
+fn main() {
+    println!("cargo:rerun-if-changed={}", DESCRIPTION);
+    println!("cargo:rerun-if-changed=build.rs");
+
+    let text = std::fs::read_to_string(DESCRIPTION).unwrap();
+    let db: toml::Table = text.parse().unwrap();
+    let registers: Vec<&toml::Table> = db["register"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|reg| reg.as_table().unwrap())
+        .collect();
+
+    let mut out = String::new();
+    let mut types = BTreeSet::new();
+    let mut new_type = |name: String| {
+        assert!(types.insert(name.clone()), "{} generated twice", name);
+        name
+    };
+
+    for (device, enum_name) in [
+        ("port", "PortRegister"),
+        ("global1", "Global1Register"),
+        ("global2", "Global2Register"),
+    ] {
+        let mut regs: Vec<&&toml::Table> = registers
+            .iter()
+            .filter(|reg| str_of(reg, "device") == device)
+            .collect();
+        regs.sort_by_key(|reg| reg["offset"].as_integer().unwrap());
+
+        writeln!(out, "/// Offsets of the {} registers", device).unwrap();
+        writeln!(out, "#[derive(Debug, Copy, Clone, PartialEq, Eq)]").unwrap();
+        writeln!(out, "#[repr(u8)]").unwrap();
+        writeln!(out, "pub enum {} {{", new_type(enum_name.to_string())).unwrap();
+        for reg in regs {
+            let offset = reg["offset"].as_integer().unwrap();
+            writeln!(
+                out,
+                "    {} = 0x{:02X},",
+                camel(str_of(reg, "name")),
+                offset
+            )
+            .unwrap();
+        }
+        writeln!(out, "}}\n").unwrap();
+    }
+
+    for reg in &registers {
+        let name = str_of(reg, "name");
+        let fields = reg["fields"].as_array().unwrap();
+        if fields.is_empty() {
+            continue;
+        }
+
+        writeln!(
+            out,
+            "/// Fields of {} register {}",
+            str_of(reg, "device"),
+            name
+        )
+        .unwrap();
+        writeln!(out, "#[derive(Debug, Copy, Clone, PartialEq, Eq)]").unwrap();
+        writeln!(out, "#[repr(u16)]").unwrap();
+        let type_name = new_type(camel(name));
+        writeln!(out, "pub enum {} {{", type_name).unwrap();
+        for field in fields {
+            let field = field.as_table().unwrap();
+            let field_name = str_of(field, "name");
+            let (len, shift) = bits(name, field_name, str_of(field, "bits"));
+            writeln!(
+                out,
+                "    {} = bitinfo_comb_flat!({}, {}),",
+                camel(field_name),
+                len,
+                shift
+            )
+            .unwrap();
+        }
+        writeln!(out, "}}\n").unwrap();
+        writeln!(out, "impl_into_bitinfo!({});\n", type_name).unwrap();
+
+        for field in fields {
+            let field = field.as_table().unwrap();
+            let Some(values) = field.get("values").and_then(|v| v.as_table()) else {
+                continue;
+            };
+            let field_name = str_of(field, "name");
+            let mut values: Vec<(&String, i64)> = values
+                .iter()
+                .map(|(value, num)| (value, num.as_integer().unwrap()))
+                .collect();
+            values.sort_by_key(|&(_, num)| num);
+
+            writeln!(out, "/// Settings of {}.{}", name, field_name).unwrap();
+            writeln!(out, "#[derive(Debug, Copy, Clone, PartialEq, Eq)]").unwrap();
+            writeln!(out, "#[repr(u16)]").unwrap();
+            // named after the register as well, fields of the same name are
+            // found in several registers
+            let value_type = new_type(camel(name) + &camel(field_name) + "Value");
+            writeln!(out, "pub enum {} {{", value_type).unwrap();
+            for (value, num) in values {
+                writeln!(out, "    {} = 0x{:X},", camel(value), num).unwrap();
+            }
+            writeln!(out, "}}\n").unwrap();
+        }
+    }
+
+    let dest = Path::new(&std::env::var("OUT_DIR").unwrap()).join("registers.rs");
+    std::fs::write(dest, out).unwrap();
+}
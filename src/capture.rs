@@ -0,0 +1,496 @@
+//! pcapng recording of the frames going over a transport, and reading them back
+//!
+//! Only the blocks needed for a readable capture are written: one section
+//! header, an interface description per link and an enhanced packet block per
+//! frame, flagged inbound or outbound. Reading also takes classic pcap files
+//! and hex dumps as printed by tcpdump, xxd, hexdump or wireshark.
+use std::fs::File;
+use std::io::{self, ErrorKind, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const BLOCK_SHB: u32 = 0x0A0D0D0A;
+const BLOCK_IDB: u32 = 0x00000001;
+const BLOCK_EPB: u32 = 0x00000006;
+
+const BYTE_ORDER_MAGIC: u32 = 0x1A2B3C4D;
+const LINKTYPE_ETHERNET: u16 = 1;
+
+const OPT_ENDOFOPT: u16 = 0;
+const OPT_SHB_USERAPPL: u16 = 4;
+const OPT_IF_NAME: u16 = 2;
+const OPT_EPB_FLAGS: u16 = 2;
+const OPT_IF_TSRESOL: u16 = 9;
+
+const BLOCK_SPB: u32 = 0x00000003;
+const BLOCK_OPB: u32 = 0x00000002;
+
+const PCAP_MAGIC_USEC: u32 = 0xA1B2C3D4;
+const PCAP_MAGIC_NSEC: u32 = 0xA1B23C4D;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Inbound,
+    Outbound,
+}
+
+impl Direction {
+    /// Direction bits of `epb_flags`
+    fn epb_flags(self) -> u32 {
+        match self {
+            Direction::Inbound => 0b01,
+            Direction::Outbound => 0b10,
+        }
+    }
+}
+
+/// Block body under construction, options appended behind the fixed part
+struct Block {
+    btype: u32,
+    body: Vec<u8>,
+    has_options: bool,
+}
+
+impl Block {
+    fn new(btype: u32) -> Self {
+        Self {
+            btype,
+            body: Vec::new(),
+            has_options: false,
+        }
+    }
+
+    fn put(&mut self, bytes: &[u8]) -> &mut Self {
+        self.body.extend_from_slice(bytes);
+        self.pad();
+        self
+    }
+
+    fn option(&mut self, code: u16, value: &[u8]) -> &mut Self {
+        self.has_options = true;
+        self.body.extend_from_slice(&code.to_le_bytes());
+        self.body
+            .extend_from_slice(&(value.len() as u16).to_le_bytes());
+        self.put(value)
+    }
+
+    fn pad(&mut self) {
+        while !self.body.len().is_multiple_of(4) {
+            self.body.push(0);
+        }
+    }
+
+    fn write_to<W: Write>(&mut self, out: &mut W) -> io::Result<()> {
+        if self.has_options {
+            self.body.extend_from_slice(&OPT_ENDOFOPT.to_le_bytes());
+            self.body.extend_from_slice(&0u16.to_le_bytes());
+        }
+
+        let total = (self.body.len() + 12) as u32;
+        let mut buf = Vec::with_capacity(total as usize);
+        buf.extend_from_slice(&self.btype.to_le_bytes());
+        buf.extend_from_slice(&total.to_le_bytes());
+        buf.extend_from_slice(&self.body);
+        buf.extend_from_slice(&total.to_le_bytes());
+
+        // one write per block, a capture cut short still ends on a block boundary
+        out.write_all(&buf)?;
+        out.flush()
+    }
+}
+
+/// Little endian pcapng writer with microsecond timestamps
+pub struct PcapngWriter<W: Write> {
+    out: W,
+    interfaces: u32,
+}
+
+impl<W: Write> PcapngWriter<W> {
+    /// Start a section, written right away
+    pub fn new(mut out: W) -> io::Result<Self> {
+        let userappl = format!("mrmu {}", env!("CARGO_PKG_VERSION"));
+        Block::new(BLOCK_SHB)
+            .put(&BYTE_ORDER_MAGIC.to_le_bytes())
+            .put(&[1, 0, 0, 0]) // version 1.0
+            .put(&(-1i64).to_le_bytes()) // section length unknown
+            .option(OPT_SHB_USERAPPL, userappl.as_bytes())
+            .write_to(&mut out)?;
+
+        Ok(Self { out, interfaces: 0 })
+    }
+
+    /// Describe an ethernet link, return the interface id to record frames with
+    pub fn add_interface(&mut self, name: &str) -> io::Result<u32> {
+        let mut fixed = [0u8; 8];
+        fixed[..2].copy_from_slice(&LINKTYPE_ETHERNET.to_le_bytes());
+        // reserved and snaplen 0, no limit
+
+        Block::new(BLOCK_IDB)
+            .put(&fixed)
+            .option(OPT_IF_NAME, name.as_bytes())
+            .write_to(&mut self.out)?;
+
+        self.interfaces += 1;
+        Ok(self.interfaces - 1)
+    }
+
+    pub fn write_packet(
+        &mut self,
+        ifid: u32,
+        timestamp: SystemTime,
+        direction: Direction,
+        frame: &[u8],
+    ) -> io::Result<()> {
+        let usec = timestamp
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_micros() as u64;
+
+        let mut fixed = [0u8; 20];
+        fixed[0..4].copy_from_slice(&ifid.to_le_bytes());
+        fixed[4..8].copy_from_slice(&((usec >> 32) as u32).to_le_bytes());
+        fixed[8..12].copy_from_slice(&(usec as u32).to_le_bytes());
+        fixed[12..16].copy_from_slice(&(frame.len() as u32).to_le_bytes());
+        fixed[16..20].copy_from_slice(&(frame.len() as u32).to_le_bytes());
+
+        Block::new(BLOCK_EPB)
+            .put(&fixed)
+            .put(frame)
+            .option(OPT_EPB_FLAGS, &direction.epb_flags().to_le_bytes())
+            .write_to(&mut self.out)
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+/// Capture file shared by every socket of the process
+pub struct Capture {
+    writer: Mutex<PcapngWriter<File>>,
+}
+
+impl Capture {
+    pub fn create(path: &Path) -> io::Result<Self> {
+        let writer = PcapngWriter::new(File::create(path)?)?;
+        Ok(Self {
+            writer: Mutex::new(writer),
+        })
+    }
+
+    pub fn add_interface(&self, name: &str) -> io::Result<u32> {
+        self.writer.lock().unwrap().add_interface(name)
+    }
+
+    /// Record `frame` as seen on interface `ifid` now
+    pub fn record(&self, ifid: u32, direction: Direction, frame: &[u8]) -> io::Result<()> {
+        self.writer
+            .lock()
+            .unwrap()
+            .write_packet(ifid, SystemTime::now(), direction, frame)
+    }
+}
+
+/// Frame read back from a capture
+#[derive(Debug, Clone, PartialEq)]
+pub struct CapturedFrame {
+    /// Time since the unix epoch, if the capture has one
+    pub timestamp: Option<Duration>,
+    pub direction: Option<Direction>,
+    pub data: Vec<u8>,
+}
+
+impl CapturedFrame {
+    fn new(data: Vec<u8>) -> Self {
+        Self {
+            timestamp: None,
+            direction: None,
+            data,
+        }
+    }
+}
+
+fn invalid(msg: impl Into<String>) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, msg.into())
+}
+
+/// Bounds checked reads in the byte order of the capture
+#[derive(Clone, Copy)]
+struct Reader<'a> {
+    buf: &'a [u8],
+    big_endian: bool,
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&self, off: usize, len: usize) -> io::Result<&'a [u8]> {
+        self.buf
+            .get(off..off.saturating_add(len))
+            .ok_or_else(|| invalid(format!("truncated capture at offset {off}")))
+    }
+
+    fn u16(&self, off: usize) -> io::Result<u16> {
+        let b = self.bytes(off, 2)?.try_into().unwrap();
+        Ok(match self.big_endian {
+            true => u16::from_be_bytes(b),
+            false => u16::from_le_bytes(b),
+        })
+    }
+
+    fn u32(&self, off: usize) -> io::Result<u32> {
+        let b = self.bytes(off, 4)?.try_into().unwrap();
+        Ok(match self.big_endian {
+            true => u32::from_be_bytes(b),
+            false => u32::from_le_bytes(b),
+        })
+    }
+}
+
+/// Whether `data` starts like a pcap or pcapng file
+pub fn is_capture(data: &[u8]) -> bool {
+    let Some(magic) = data.get(..4) else {
+        return false;
+    };
+    let magic = u32::from_le_bytes(magic.try_into().unwrap());
+    magic == BLOCK_SHB
+        || [PCAP_MAGIC_USEC, PCAP_MAGIC_NSEC]
+            .iter()
+            .any(|&m| magic == m || magic == m.swap_bytes())
+}
+
+/// Frames of a pcap or pcapng file, only ethernet links are supported
+pub fn read_capture(data: &[u8]) -> io::Result<Vec<CapturedFrame>> {
+    let magic = u32::from_le_bytes(
+        data.get(..4)
+            .ok_or_else(|| invalid("not a capture file"))?
+            .try_into()
+            .unwrap(),
+    );
+    match magic {
+        BLOCK_SHB => read_pcapng(data),
+        _ => read_pcap(data),
+    }
+}
+
+fn read_pcap(data: &[u8]) -> io::Result<Vec<CapturedFrame>> {
+    let le = Reader {
+        buf: data,
+        big_endian: false,
+    };
+    let magic = le.u32(0)?;
+    let (big_endian, nsec) = match magic {
+        PCAP_MAGIC_USEC => (false, false),
+        PCAP_MAGIC_NSEC => (false, true),
+        m if m == PCAP_MAGIC_USEC.swap_bytes() => (true, false),
+        m if m == PCAP_MAGIC_NSEC.swap_bytes() => (true, true),
+        _ => return Err(invalid("not a pcap file")),
+    };
+    let r = Reader {
+        buf: data,
+        big_endian,
+    };
+
+    let linktype = r.u32(20)? & 0xFFFF;
+    if linktype != LINKTYPE_ETHERNET as u32 {
+        return Err(invalid(format!("unsupported linktype {linktype}")));
+    }
+
+    let mut frames = Vec::new();
+    let mut off = 24;
+    while off < data.len() {
+        let sec = r.u32(off)? as u64;
+        let frac = r.u32(off + 4)?;
+        let caplen = r.u32(off + 8)? as usize;
+        let subsec = match nsec {
+            true => Duration::from_nanos(frac as u64),
+            false => Duration::from_micros(frac as u64),
+        };
+
+        let mut frame = CapturedFrame::new(r.bytes(off + 16, caplen)?.to_vec());
+        frame.timestamp = Some(Duration::from_secs(sec) + subsec);
+        frames.push(frame);
+        off += 16 + caplen;
+    }
+
+    Ok(frames)
+}
+
+/// Interface of a pcapng section as far as reading frames goes
+struct Interface {
+    linktype: u16,
+    // timestamp units per second
+    units: u64,
+}
+
+/// Walk the options of a block, `f` gets code and value of each one
+fn for_each_option(
+    r: Reader,
+    mut off: usize,
+    end: usize,
+    mut f: impl FnMut(u16, &[u8]),
+) -> io::Result<()> {
+    while off + 4 <= end {
+        let code = r.u16(off)?;
+        let len = r.u16(off + 2)? as usize;
+        if code == OPT_ENDOFOPT {
+            break;
+        }
+        f(code, r.bytes(off + 4, len)?);
+        off += 4 + len.next_multiple_of(4);
+    }
+    Ok(())
+}
+
+fn read_pcapng(data: &[u8]) -> io::Result<Vec<CapturedFrame>> {
+    let mut r = Reader {
+        buf: data,
+        big_endian: false,
+    };
+    let mut interfaces: Vec<Interface> = Vec::new();
+    let mut frames = Vec::new();
+
+    let mut off = 0;
+    while off < data.len() {
+        let btype = r.u32(off)?;
+        if btype == BLOCK_SHB {
+            // every section brings its own byte order and interfaces
+            r.big_endian = match r.u32(off + 8)? {
+                BYTE_ORDER_MAGIC => r.big_endian,
+                m if m == BYTE_ORDER_MAGIC.swap_bytes() => !r.big_endian,
+                _ => return Err(invalid("bad pcapng byte order magic")),
+            };
+            interfaces.clear();
+        }
+
+        let len = r.u32(off + 4)? as usize;
+        if len < 12 || !len.is_multiple_of(4) {
+            return Err(invalid(format!("bad pcapng block length at offset {off}")));
+        }
+        // fixed part of the block with its trailing length
+        let header = match btype {
+            BLOCK_EPB | BLOCK_OPB => 32,
+            BLOCK_IDB => 20,
+            BLOCK_SPB => 16,
+            _ => 12,
+        };
+        if len < header {
+            return Err(invalid(format!(
+                "pcapng block at offset {off} shorter than its header"
+            )));
+        }
+        let end = off + len - 4;
+        r.bytes(off, len)?;
+
+        match btype {
+            BLOCK_IDB => {
+                let mut iface = Interface {
+                    linktype: r.u16(off + 8)?,
+                    units: 1_000_000,
+                };
+                for_each_option(r, off + 16, end, |code, value| {
+                    if code == OPT_IF_TSRESOL && value.len() == 1 {
+                        let exp = (value[0] & 0x7F) as u32;
+                        let base: u64 = if value[0] & 0x80 != 0 { 2 } else { 10 };
+                        iface.units = base.checked_pow(exp).unwrap_or(u64::MAX);
+                    }
+                })?;
+                interfaces.push(iface);
+            }
+            BLOCK_EPB | BLOCK_OPB => {
+                let ifid = match btype {
+                    BLOCK_EPB => r.u32(off + 8)? as usize,
+                    _ => r.u16(off + 8)? as usize,
+                };
+                let iface = interfaces
+                    .get(ifid)
+                    .ok_or_else(|| invalid(format!("unknown interface {ifid}")))?;
+                let ts = ((r.u32(off + 12)? as u64) << 32) | r.u32(off + 16)? as u64;
+                let caplen = (r.u32(off + 20)? as usize).min(end - (off + 28));
+
+                if iface.linktype == LINKTYPE_ETHERNET {
+                    let mut frame = CapturedFrame::new(r.bytes(off + 28, caplen)?.to_vec());
+                    let nanos = (ts % iface.units) as u128 * 1_000_000_000 / iface.units as u128;
+                    frame.timestamp = Some(
+                        Duration::from_secs(ts / iface.units) + Duration::from_nanos(nanos as u64),
+                    );
+                    if btype == BLOCK_EPB {
+                        let opts = off + 28 + caplen.next_multiple_of(4);
+                        for_each_option(r, opts, end, |code, value| {
+                            if code == OPT_EPB_FLAGS && value.len() == 4 {
+                                let value = Reader { buf: value, ..r };
+                                frame.direction = match value.u32(0).unwrap_or(0) & 0b11 {
+                                    0b01 => Some(Direction::Inbound),
+                                    0b10 => Some(Direction::Outbound),
+                                    _ => None,
+                                };
+                            }
+                        })?;
+                    }
+                    frames.push(frame);
+                }
+            }
+            // no timestamp, captured length is bounded by the block
+            BLOCK_SPB if interfaces.first().map(|i| i.linktype) == Some(LINKTYPE_ETHERNET) => {
+                let origlen = r.u32(off + 8)? as usize;
+                let caplen = origlen.min(end - (off + 12));
+                frames.push(CapturedFrame::new(r.bytes(off + 12, caplen)?.to_vec()));
+            }
+            _ => (),
+        }
+
+        off += len;
+    }
+
+    Ok(frames)
+}
+
+/// Frames of a hex dump
+///
+/// A leading offset column (`0x0010:`, `00000010:` or followed by two
+/// spaces) and a trailing ascii column are skipped. Frames are separated by
+/// blank lines or start over at offset 0.
+pub fn parse_hexdump(text: &str) -> io::Result<Vec<CapturedFrame>> {
+    let mut frames = Vec::new();
+    let mut data = Vec::new();
+
+    for (lineno, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            if !data.is_empty() {
+                frames.push(CapturedFrame::new(std::mem::take(&mut data)));
+            }
+            continue;
+        }
+
+        let mut rest = line;
+        let first = line.split_whitespace().next().unwrap_or_default();
+        let after = &line[first.len()..];
+        let is_offset = first.ends_with(':')
+            || (after.starts_with("  ") && after.trim_start().contains(char::is_whitespace));
+        if is_offset {
+            let offset = first.trim_end_matches(':');
+            let offset = offset.strip_prefix("0x").unwrap_or(offset);
+            if u64::from_str_radix(offset, 16) == Ok(0) && !data.is_empty() {
+                frames.push(CapturedFrame::new(std::mem::take(&mut data)));
+            }
+            rest = after;
+        }
+
+        // the ascii column ends the hex digits
+        for token in rest.split_whitespace() {
+            if token.len() % 2 != 0 || !token.chars().all(|c| c.is_ascii_hexdigit()) {
+                break;
+            }
+            for i in (0..token.len()).step_by(2) {
+                let byte = u8::from_str_radix(&token[i..i + 2], 16)
+                    .map_err(|_| invalid(format!("line {}: bad hex", lineno + 1)))?;
+                data.push(byte);
+            }
+        }
+    }
+
+    if !data.is_empty() {
+        frames.push(CapturedFrame::new(data));
+    }
+    Ok(frames)
+}
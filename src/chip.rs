@@ -0,0 +1,147 @@
+//! What the supported switches have, picked by the product number every
+//! response header and the SwitchIdentifier register carry
+use serde::Deserialize;
+
+use crate::reginfo::PortStatusCModeValue;
+
+/// Interface behind a port, as the c_mode of its port_status tells
+#[derive(Debug, Clone, Copy, PartialEq, Eq, strum_macros::Display)]
+#[strum(serialize_all = "lowercase")]
+pub enum PortKind {
+    /// 100/1000BASE-T1 automotive PHY
+    T1,
+    Sgmii,
+    Rgmii,
+    /// Port to the internal CPU
+    Cpu,
+}
+
+impl PortKind {
+    /// Kind of a port by the c_mode field of its port_status, `None` for modes
+    /// that are none of these
+    pub fn from_c_mode(c_mode: u16) -> Option<Self> {
+        [
+            (PortStatusCModeValue::T1Phy, Self::T1),
+            (PortStatusCModeValue::Sgmii, Self::Sgmii),
+            (PortStatusCModeValue::Rgmii, Self::Rgmii),
+            (PortStatusCModeValue::Cpu, Self::Cpu),
+        ]
+        .into_iter()
+        .find(|&(mode, _)| mode as u16 == c_mode)
+        .map(|(_, kind)| kind)
+    }
+}
+
+/// Blocks not every chip has
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, strum_macros::Display)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum Feature {
+    Avb,
+    Tcam,
+    /// 802.1Qbu frame preemption
+    Preemption,
+    Macsec,
+}
+
+/// Capabilities of one switch model
+#[derive(Debug, PartialEq, Eq)]
+pub struct ChipProfile {
+    pub model: &'static str,
+    /// Product number without the revision nibble
+    pub product_num: u16,
+    /// Ports 0 up to this, `PortKind::from_c_mode` tells what is behind each
+    /// as that depends on the strapping of the board
+    pub port_count: u8,
+    pub features: &'static [Feature],
+}
+
+/// Ports assumed of products without a profile
+pub const DEFAULT_PORT_COUNT: u8 = 10;
+
+pub static PROFILES: [ChipProfile; 3] = [
+    ChipProfile {
+        model: "88Q5151",
+        product_num: 0x151,
+        port_count: 10,
+        features: &[Feature::Avb, Feature::Tcam],
+    },
+    ChipProfile {
+        model: "88Q5152",
+        product_num: 0x152,
+        port_count: 10,
+        features: &[
+            Feature::Avb,
+            Feature::Tcam,
+            Feature::Preemption,
+            Feature::Macsec,
+        ],
+    },
+    ChipProfile {
+        model: "88Q5192",
+        product_num: 0x192,
+        port_count: 10,
+        features: &[Feature::Avb, Feature::Tcam, Feature::Preemption],
+    },
+];
+
+/// A detected switch: its profile and silicon revision
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Chip {
+    pub profile: &'static ChipProfile,
+    pub revision: u8,
+}
+
+impl Chip {
+    /// Chip of a product number as in the response header, e.g. 0x1521 for
+    /// 88Q5152 revision 1, `None` for unknown products
+    pub fn from_product_number(product_number: u16) -> Option<Self> {
+        let product_num = product_number >> 4;
+        PROFILES
+            .iter()
+            .find(|profile| profile.product_num == product_num)
+            .map(|profile| Self {
+                profile,
+                revision: (product_number & 0x000F) as u8,
+            })
+    }
+
+    pub fn model(&self) -> &'static str {
+        self.profile.model
+    }
+
+    pub fn port_count(&self) -> u8 {
+        self.profile.port_count
+    }
+
+    pub fn has(&self, feature: Feature) -> bool {
+        self.profile.features.contains(&feature)
+    }
+
+    /// Error text unless `port` exists on the chip
+    pub fn check_port(&self, port: u8) -> Result<(), String> {
+        if port < self.port_count() {
+            return Ok(());
+        }
+        Err(format!(
+            "{} has ports 0-{}, no port {}",
+            self.model(),
+            self.port_count() - 1,
+            port
+        ))
+    }
+
+    /// Error text unless the chip has `feature`
+    pub fn check_feature(&self, feature: Feature) -> Result<(), String> {
+        if self.has(feature) {
+            return Ok(());
+        }
+        Err(format!("{} has no {}", self.model(), feature))
+    }
+}
+
+impl std::fmt::Display for Chip {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{} rev {}", self.model(), self.revision)
+    }
+}
@@ -0,0 +1,344 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::io::ErrorKind;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::error::RmuError;
+use crate::message::customer_info_read::{CustomerInfoReadRequest, CustomerInfoReadResponse};
+use crate::message::fw_version::{FwVersionRequest, FwVersionResponse};
+use crate::message::getid::{GetIdRequest, GetIdResponse};
+use crate::message::header::{RequestHeader, ResponseHeader};
+use crate::message::register::{
+    RegOpRequestList, RegisterRequest, RegisterResponse, MAX_REGOPS_PER_FRAME,
+};
+use crate::message::response_error::DeviceError;
+use crate::message::version_read::{VersionReadRequest, VersionReadResponse};
+use crate::message::{self, MessageHeaderOperation, MessageOperation, RMU_MULTICAST_ADDR};
+use crate::message_builder::MessageBuilder;
+use crate::packet_sock::ETH_P_RMU;
+use crate::transport::Transport;
+
+/// Answer of one device, the expected response or the error response it sent
+pub type Reply<T> = Result<T, DeviceError>;
+
+/// Request/response exchange with RMU devices over a `Transport`
+///
+/// Every request gets the next sequence number, counted from a random start so
+/// that two processes on one link do not share them. Only frames carrying it,
+/// from the addressed devid and, unless sent to multicast, from the addressed
+/// mac count as reply; anything else on the link is dropped. Register replies
+/// must also echo the ops of the request.
+pub struct RmuClient<T> {
+    transport: T,
+    timeout: Duration,
+    retries: u32,
+    max_regops: usize,
+    seqno: AtomicU8,
+}
+
+impl<T: Transport> RmuClient<T> {
+    pub fn new(transport: T) -> Self {
+        Self {
+            transport,
+            timeout: Duration::from_millis(100),
+            retries: 0,
+            max_regops: MAX_REGOPS_PER_FRAME,
+            seqno: AtomicU8::new(RandomState::new().build_hasher().finish() as u8),
+        }
+    }
+
+    /// Time to wait for replies, per attempt
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+    pub fn set_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Attempts after the first one when no reply came back
+    ///
+    /// A retry sends the request again under a new sequence number, so only
+    /// enable it for requests that are safe to repeat. An ATU GetNext is not.
+    pub fn retries(&self) -> u32 {
+        self.retries
+    }
+    pub fn set_retries(&mut self, retries: u32) -> &mut Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Register ops sent per RwRegister frame, longer lists take several
+    pub fn max_regops(&self) -> usize {
+        self.max_regops
+    }
+    /// Lower the ops per frame for firmware taking fewer than fit the frame
+    pub fn set_max_regops(&mut self, max_regops: usize) -> &mut Self {
+        self.max_regops = max_regops.clamp(1, MAX_REGOPS_PER_FRAME);
+        self
+    }
+
+    pub fn transport(&self) -> &T {
+        &self.transport
+    }
+
+    pub fn next_sequence_number(&self) -> u8 {
+        self.seqno.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// Header of a request to `devid` behind `dmac`, sent from the transport mac
+    pub fn request_header(&self, dmac: &[u8; 6], devid: u8) -> MessageBuilder<RequestHeader> {
+        MessageBuilder::<RequestHeader>::new()
+            .destination_address(dmac)
+            .source_address(&self.transport.source_address())
+            .device_id(devid)
+    }
+
+    /// Send `req` and collect the replies until the timeout passed
+    ///
+    /// A request to the multicast address can be answered by several devices.
+    pub async fn request<Req, Resp>(&self, req: &mut Req) -> Result<Vec<Reply<Resp>>, RmuError>
+    where
+        Req: MessageOperation<Header = RequestHeader>,
+        Resp: MessageOperation<Output = Resp>,
+    {
+        self.exchange(req, true, |_| true).await
+    }
+
+    /// Send `req` and return the first reply, `RmuError::Timeout` if none came
+    pub async fn request_one<Req, Resp>(&self, req: &mut Req) -> Result<Resp, RmuError>
+    where
+        Req: MessageOperation<Header = RequestHeader>,
+        Resp: MessageOperation<Output = Resp>,
+    {
+        match self.exchange(req, false, |_| true).await?.pop() {
+            Some(reply) => Ok(reply?),
+            None => Err(RmuError::Timeout),
+        }
+    }
+
+    async fn exchange<Req, Resp>(
+        &self,
+        req: &mut Req,
+        collect: bool,
+        accept: impl Fn(&Resp) -> bool,
+    ) -> Result<Vec<Reply<Resp>>, RmuError>
+    where
+        Req: MessageOperation<Header = RequestHeader>,
+        Resp: MessageOperation<Output = Resp>,
+    {
+        for _ in 0..=self.retries {
+            req.header_mut()
+                .set_sequence_number(self.next_sequence_number());
+
+            let mut wbuf = message::prealloc_buffer(req);
+            req.marshal(&mut wbuf)?;
+            self.transport.send(&wbuf).await?;
+
+            let deadline = Instant::now() + self.timeout;
+            let mut replies = Vec::new();
+            loop {
+                let mut rbuf = [0; 1514];
+                let sz = match self.transport.recv(&mut rbuf, deadline).await {
+                    Ok(sz) => sz,
+                    Err(e) if e.kind() == ErrorKind::TimedOut => break,
+                    Err(e) => return Err(e.into()),
+                };
+
+                if !is_reply_to(req.header(), &rbuf[..sz]) {
+                    continue;
+                }
+
+                match message::unmarshal::<Resp>(&rbuf[..sz]) {
+                    Ok(resp) if accept(&resp) => replies.push(Ok(resp)),
+                    Ok(_) => continue,
+                    Err(RmuError::DeviceError(e)) => replies.push(Err(e)),
+                    Err(e) => return Err(e),
+                }
+
+                if !collect {
+                    break;
+                }
+            }
+
+            if !replies.is_empty() {
+                return Ok(replies);
+            }
+        }
+
+        Ok(Vec::new())
+    }
+
+    pub async fn get_id(
+        &self,
+        dmac: &[u8; 6],
+        devid: u8,
+    ) -> Result<Vec<Reply<GetIdResponse>>, RmuError> {
+        let mut req =
+            Into::<MessageBuilder<GetIdRequest>>::into(self.request_header(dmac, devid)).build()?;
+        self.request(&mut req).await
+    }
+
+    /// GetId expecting one reply, e.g. to learn the product number
+    pub async fn get_id_one(&self, dmac: &[u8; 6], devid: u8) -> Result<GetIdResponse, RmuError> {
+        let mut req =
+            Into::<MessageBuilder<GetIdRequest>>::into(self.request_header(dmac, devid)).build()?;
+        self.request_one(&mut req).await
+    }
+
+    pub async fn version_read(
+        &self,
+        dmac: &[u8; 6],
+        devid: u8,
+    ) -> Result<Vec<Reply<VersionReadResponse>>, RmuError> {
+        let mut req =
+            Into::<MessageBuilder<VersionReadRequest>>::into(self.request_header(dmac, devid))
+                .build()?;
+        self.request(&mut req).await
+    }
+
+    pub async fn customer_info_read(
+        &self,
+        dmac: &[u8; 6],
+        devid: u8,
+    ) -> Result<Vec<Reply<CustomerInfoReadResponse>>, RmuError> {
+        let mut req =
+            Into::<MessageBuilder<CustomerInfoReadRequest>>::into(self.request_header(dmac, devid))
+                .build()?;
+        self.request(&mut req).await
+    }
+
+    pub async fn fw_version_get(
+        &self,
+        dmac: &[u8; 6],
+        devid: u8,
+    ) -> Result<Vec<Reply<FwVersionResponse>>, RmuError> {
+        let mut req =
+            Into::<MessageBuilder<FwVersionRequest>>::into(self.request_header(dmac, devid))
+                .build()?;
+        self.request(&mut req).await
+    }
+
+    /// Register ops, answered by every device behind `dmac`
+    ///
+    /// A list longer than `max_regops` goes out as consecutive frames and the
+    /// answers of each device are joined into one response, under the header
+    /// of its first answer. As a device stops at a WaitOnBit that does not
+    /// settle, the next frame only goes out while some device ran all ops so
+    /// far. A device missing a later frame keeps the ops answered until then,
+    /// one answering it with an error ends up with that error.
+    pub async fn register_ops(
+        &self,
+        dmac: &[u8; 6],
+        devid: u8,
+        regops: RegOpRequestList,
+    ) -> Result<Vec<Reply<RegisterResponse>>, RmuError> {
+        let mut joined: Vec<Reply<RegisterResponse>> = Vec::new();
+        let mut sent = 0;
+        for (i, chunk) in regops.split(self.max_regops).into_iter().enumerate() {
+            let len = chunk.len();
+            let mut req = self.register_request(dmac, devid, chunk.clone())?;
+            let replies = self
+                .exchange(&mut req, true, |resp: &RegisterResponse| {
+                    resp.regops.answers(&chunk)
+                })
+                .await?;
+            if i == 0 {
+                joined = replies;
+            } else {
+                for reply in replies {
+                    join_reply(&mut joined, reply, sent);
+                }
+            }
+
+            sent += len;
+            let ran_all = |reply: &Reply<RegisterResponse>| match reply {
+                Ok(resp) => resp.regops.ran_all(sent),
+                Err(_) => false,
+            };
+            if !joined.iter().any(ran_all) {
+                break;
+            }
+        }
+        Ok(joined)
+    }
+
+    /// Register ops expecting one reply, e.g. to a device's own mac
+    ///
+    /// Split and joined as with `register_ops`.
+    pub async fn register_ops_one(
+        &self,
+        dmac: &[u8; 6],
+        devid: u8,
+        regops: RegOpRequestList,
+    ) -> Result<RegisterResponse, RmuError> {
+        let mut joined: Option<RegisterResponse> = None;
+        for chunk in regops.split(self.max_regops) {
+            let len = chunk.len();
+            let mut req = self.register_request(dmac, devid, chunk.clone())?;
+            let resp = self
+                .exchange(&mut req, false, |resp: &RegisterResponse| {
+                    resp.regops.answers(&chunk)
+                })
+                .await?
+                .pop()
+                .ok_or(RmuError::Timeout)??;
+            let stopped = !resp.regops.ran_all(len);
+            match joined.as_mut() {
+                Some(first) => first.regops.append(resp.regops),
+                None => joined = Some(resp),
+            }
+            if stopped {
+                break;
+            }
+        }
+        Ok(joined.expect("split gives one list at least"))
+    }
+
+    fn register_request(
+        &self,
+        dmac: &[u8; 6],
+        devid: u8,
+        regops: RegOpRequestList,
+    ) -> Result<RegisterRequest, RmuError> {
+        Into::<MessageBuilder<RegisterRequest>>::into(self.request_header(dmac, devid))
+            .regops(regops)
+            .build()
+    }
+}
+
+/// Add the answer to a later frame of a split op list to the one of the same
+/// device, if that device ran all `sent` ops before
+fn join_reply(joined: &mut [Reply<RegisterResponse>], reply: Reply<RegisterResponse>, sent: usize) {
+    let mac = match &reply {
+        Ok(resp) => resp.header.source_address(),
+        Err(e) => e.mac,
+    };
+    let Some(known) = joined.iter_mut().find(|known| match known {
+        Ok(resp) => resp.header.source_address() == mac && resp.regops.ran_all(sent),
+        Err(_) => false,
+    }) else {
+        return;
+    };
+
+    match (known, reply) {
+        (Ok(first), Ok(resp)) => first.regops.append(resp.regops),
+        (known, reply) => *known = reply,
+    }
+}
+
+/// Whether `frame` is a RMU response to `req`
+pub(crate) fn is_reply_to(req: &RequestHeader, frame: &[u8]) -> bool {
+    let Ok(resp) = ResponseHeader::unmarshal(frame) else {
+        return false;
+    };
+
+    let dmac = req.destination_address();
+    // to_cpu tag, a request carries from_cpu
+    resp.ether_type() == ETH_P_RMU as u16
+        && frame[16] & 0xC0 == 0x00
+        && resp.sequence_number() == req.sequence_number()
+        && resp.device_id() == req.device_id()
+        && (dmac == RMU_MULTICAST_ADDR || resp.source_address() == dmac)
+}
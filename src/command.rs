@@ -1,25 +1,58 @@
+mod atu;
 mod customer_info_read;
+mod decode;
+mod diff;
+mod dump;
 mod fw_version_get;
+mod monitor;
 mod read_atu;
+mod read_global1;
+mod read_global2;
 mod read_port;
 mod read_vtu;
 mod regop;
+mod restore;
 mod scan;
+mod simulate;
 mod verinfo;
 mod version_read;
+mod write_port;
 
+use atu::AtuCmd;
 use customer_info_read::CustomerInfoReadCmd;
+use decode::DecodeCmd;
+use diff::DiffCmd;
+use dump::DumpCmd;
 use fw_version_get::FwVersionGetCmd;
+use monitor::MonitorCmd;
 use read_atu::ReadAtuCmd;
+use read_global1::ReadGlobal1Cmd;
+use read_global2::ReadGlobal2Cmd;
 use read_port::ReadPortRegCmd;
 use read_vtu::ReadVtuCmd;
 use regop::RegOpCmd;
+use restore::RestoreCmd;
 use scan::ScanCmd;
+use simulate::SimulateCmd;
 use verinfo::SoftwareInfoCmd;
 use version_read::VersionReadCmd;
+use write_port::WritePortRegCmd;
 
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use anyhow::anyhow;
 use clap::Subcommand;
 
+use mrmu::chip::Chip;
+use mrmu::message::register::{RegOpRequest, RegOpRequestList, RegOpResponse};
+use mrmu::message::MessageOperation;
+use mrmu::message::RMU_MULTICAST_ADDR;
+use mrmu::packet_sock::PacketSock;
+use mrmu::reginfo::{u16_set_bits, Access, Field, RegisterDesc};
+use mrmu::transport::Transport;
+use mrmu::RmuClient;
+
 // @todo: impl future
 #[derive(Subcommand, Debug)]
 pub enum Commands {
@@ -30,17 +63,24 @@ pub enum Commands {
     SoftwareInfo(SoftwareInfoCmd),
     Regop(RegOpCmd),
     ReadAtu(ReadAtuCmd),
+    Atu(AtuCmd),
     ReadVtu(ReadVtuCmd),
     ReadPort(ReadPortRegCmd),
+    ReadGlobal1(ReadGlobal1Cmd),
+    ReadGlobal2(ReadGlobal2Cmd),
+    WritePort(WritePortRegCmd),
+    Simulate(SimulateCmd),
+    Decode(DecodeCmd),
+    Monitor(MonitorCmd),
+    Dump(DumpCmd),
+    Diff(DiffCmd),
+    Restore(RestoreCmd),
 }
 
-// @todo: future poll api
 pub trait CommandOperation {
     fn process(&self) -> anyhow::Result<()>;
 }
 
-const RMU_MULTICAST_ADDR: [u8; 6] = [0x01, 0x50, 0x43, 0x00, 0x00, 0x03];
-
 impl CommandOperation for Commands {
     fn process(&self) -> anyhow::Result<()> {
         match self {
@@ -51,8 +91,205 @@ impl CommandOperation for Commands {
             Commands::SoftwareInfo(m) => m.process(),
             Commands::Regop(m) => m.process(),
             Commands::ReadAtu(m) => m.process(),
+            Commands::Atu(m) => m.process(),
             Commands::ReadVtu(m) => m.process(),
             Commands::ReadPort(m) => m.process(),
+            Commands::ReadGlobal1(m) => m.process(),
+            Commands::ReadGlobal2(m) => m.process(),
+            Commands::WritePort(m) => m.process(),
+            Commands::Simulate(m) => m.process(),
+            Commands::Decode(m) => m.process(),
+            Commands::Monitor(m) => m.process(),
+            Commands::Dump(m) => m.process(),
+            Commands::Diff(m) => m.process(),
+            Commands::Restore(m) => m.process(),
+        }
+    }
+}
+
+/// Retries and op list limit of every client, set from the global flags
+static CLIENT_LIMITS: OnceLock<(u32, usize)> = OnceLock::new();
+
+pub fn set_client_limits(retries: u32, max_regops: usize) {
+    let _ = CLIENT_LIMITS.set((retries, max_regops));
+}
+
+/// Client on `interface` waiting `timeout_ms` for replies, with the limits of
+/// the global flags
+fn open_client(interface: &str, timeout_ms: u32) -> anyhow::Result<RmuClient<PacketSock>> {
+    let mut client = RmuClient::new(PacketSock::open(interface)?);
+    client.set_timeout(Duration::from_millis(timeout_ms.into()));
+    if let Some(&(retries, max_regops)) = CLIENT_LIMITS.get() {
+        client.set_retries(retries).set_max_regops(max_regops);
+    }
+    Ok(client)
+}
+
+/// Print the fields of register `name`, only the ones named in `fields` if given
+fn print_register(
+    name: &str,
+    val: u16,
+    layout: &[Field],
+    fields: &Option<Vec<String>>,
+) -> anyhow::Result<()> {
+    if let Some(names) = fields {
+        for wanted in names {
+            if !layout.iter().any(|field| &field.name == wanted) {
+                let known: Vec<&str> = layout.iter().map(|f| f.name.as_str()).collect();
+                return Err(anyhow!(
+                    "no field {} in {}, one of: {}",
+                    wanted,
+                    name,
+                    known.join(",")
+                ));
+            }
+        }
+    }
+
+    println!("{}:", name);
+    for field in layout {
+        if fields
+            .as_ref()
+            .is_some_and(|names| !names.contains(&field.name))
+        {
+            continue;
+        }
+        let value = field.get(val);
+        match field.value_name(value) {
+            Some(setting) => println!(" {} {} ({})", field.name, value, setting),
+            None => println!(" {} {}", field.name, value),
         }
     }
+    Ok(())
+}
+
+/// Apply `field=value` assignments to `val` of register `name`, value a number or setting name
+fn set_fields(name: &str, mut val: u16, layout: &[Field], sets: &[String]) -> anyhow::Result<u16> {
+    for set in sets {
+        let Some((wanted, value)) = set.split_once('=') else {
+            return Err(anyhow!(
+                "wrong field assignment {}, field=value expected",
+                set
+            ));
+        };
+        let Some(field) = layout.iter().find(|field| field.name == wanted) else {
+            let known: Vec<&str> = layout.iter().map(|f| f.name.as_str()).collect();
+            return Err(anyhow!(
+                "no field {} in {}, one of: {}",
+                wanted,
+                name,
+                known.join(",")
+            ));
+        };
+        if field.access == Access::Ro {
+            return Err(anyhow!("{}.{} is read only", name, wanted));
+        }
+        let value = match field.value_of(value) {
+            Some(setting) => setting,
+            None => clap_num::maybe_hex::<u16>(value)
+                .map_err(|e| anyhow!("{} value {}: {}", wanted, value, e))?,
+        };
+        if u32::from(value) >> field.info.len != 0 {
+            return Err(anyhow!(
+                "{} is {} bits wide, 0x{:X} does not fit",
+                wanted,
+                field.info.len,
+                value
+            ));
+        }
+        val = u16_set_bits(val, value, field.info);
+    }
+    Ok(val)
+}
+
+/// Chip at `devid` by the product number of its GetId reply, `None` if unknown
+async fn detect_chip<T: Transport>(
+    client: &RmuClient<T>,
+    dmac: &[u8; 6],
+    devid: u8,
+) -> anyhow::Result<Option<Chip>> {
+    let prodno = client
+        .get_id_one(dmac, devid)
+        .await?
+        .header()
+        .product_number();
+    Ok(chip_of(prodno))
+}
+
+/// Profile of product `prodno`, warning when there is none
+fn chip_of(prodno: u16) -> Option<Chip> {
+    let chip = Chip::from_product_number(prodno);
+    if chip.is_none() {
+        eprintln!(
+            "unknown product 0x{:04X}, ports and features unchecked",
+            prodno
+        );
+    }
+    chip
+}
+
+/// Refuse registers of blocks or ports `chip` does not have
+fn check_register(chip: Option<Chip>, desc: &RegisterDesc, port: Option<u8>) -> anyhow::Result<()> {
+    let Some(chip) = chip else {
+        return Ok(());
+    };
+    if let Some(port) = port {
+        chip.check_port(port).map_err(anyhow::Error::msg)?;
+    }
+    if let Some(feature) = desc.feature {
+        chip.check_feature(feature)
+            .map_err(|e| anyhow!("{}: {}", desc.name, e))?;
+    }
+    Ok(())
+}
+
+/// Read registers `regs` of SMI device `addr` in one frame
+async fn read_block<T: Transport>(
+    client: &RmuClient<T>,
+    dmac: &[u8; 6],
+    devid: u8,
+    addr: u8,
+    regs: &[u8],
+) -> anyhow::Result<Vec<u16>> {
+    let mut regops = RegOpRequestList::new();
+    for &reg in regs {
+        regops.add_regop(RegOpRequest::Read { addr, reg });
+    }
+
+    let resp = client.register_ops_one(dmac, devid, regops).await?;
+    let values: Vec<u16> = resp
+        .regops
+        .as_ref()
+        .iter()
+        .filter_map(|op| match *op {
+            RegOpResponse::Read { data, .. } => Some(data),
+            _ => None,
+        })
+        .collect();
+    if values.len() != regs.len() {
+        return Err(anyhow!(
+            "read of SMI device 0x{:02X}: {} of {} registers answered",
+            addr,
+            values.len(),
+            regs.len()
+        ));
+    }
+    Ok(values)
+}
+
+/// Read register `desc` of SMI device `addr` and print its fields, the raw value too with `print_reg`
+async fn read_and_print<T: Transport>(
+    client: &RmuClient<T>,
+    dmac: &[u8; 6],
+    devid: u8,
+    addr: u8,
+    desc: &RegisterDesc,
+    fields: &Option<Vec<String>>,
+    print_reg: bool,
+) -> anyhow::Result<()> {
+    let val = read_block(client, dmac, devid, addr, &[desc.offset]).await?[0];
+    if print_reg {
+        println!("{}(H): {:04X}", desc.name, val);
+    }
+    print_register(&desc.name, val, &desc.fields, fields)
 }
@@ -0,0 +1,752 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use anyhow::anyhow;
+use clap::{Args, Subcommand};
+use mac_address::MacAddress;
+
+use mrmu::chip::{Chip, DEFAULT_PORT_COUNT};
+use mrmu::message::register::{RegOpRequest, RegOpRequestList, RegOpResponse};
+use mrmu::reginfo::{u16_get_bits, u16_set_bits, AtuData, AtuFid, AtuOperation};
+use mrmu::reginfo::{AtuOperationAtuOpValue, Global1Register as G1, GLOBAL1_ADDR};
+use mrmu::transport::Transport;
+use mrmu::RmuClient;
+
+use super::{detect_chip, open_client, CommandOperation};
+
+// entry states of AtuData, unicast ages from 7 down to 1
+const STATE_PURGE: u8 = 0x0;
+const STATE_UC_DYNAMIC: u8 = 0x7;
+const STATE_MC_STATIC: u8 = 0x7;
+const STATE_UC_STATIC: u8 = 0xF;
+
+// flush and move: state 0 takes every port, 0xF the from port in the low
+// nibble of the port vector to the port in the next one, port 0xF is none
+const STATE_FLUSH_ALL: u8 = 0x0;
+const STATE_FLUSH_MOVE_PORT: u8 = 0xF;
+const PORT_NONE: u8 = 0xF;
+
+/// Change and look up ATU entries through the Global1 ATU operation register
+#[derive(Args, Debug)]
+pub struct AtuCmd {
+    #[arg(short, long)]
+    interface: String,
+
+    #[arg(short, long, default_value_t = 100)]
+    timeout_ms: u32,
+
+    /// Where requests go
+    #[arg(short = 'm', long = "dest")]
+    #[arg(default_value_t = String::from("01:50:43:00:00:03"))]
+    dest: String,
+
+    #[arg(short, long, value_parser=clap_num::maybe_hex::<u8>)]
+    devid: u8,
+
+    #[command(subcommand)]
+    op: AtuOp,
+}
+
+#[derive(Subcommand, Debug)]
+enum AtuOp {
+    /// Load an entry, replacing the one of the same fid and mac
+    Add(AtuAddArgs),
+    /// Purge an entry
+    Del(AtuDelArgs),
+    /// Flush entries, all of them unless narrowed down
+    Flush(AtuFlushArgs),
+    /// Move the entries of one port to another
+    Move(AtuMoveArgs),
+    /// Walk the ATU over and over, printing the entries added, removed and moved
+    Watch(AtuWatchArgs),
+}
+
+#[derive(Args, Debug)]
+struct AtuAddArgs {
+    #[arg(long, default_value_t = 0, value_parser = clap::value_parser!(u16).range(0..4096))]
+    fid: u16,
+
+    /// Address of the entry
+    #[arg(long)]
+    mac: MacAddress,
+
+    /// Comma separated ports frames to the address go to
+    #[arg(long, required = true, num_args = 1, value_delimiter = ',')]
+    ports: Vec<u8>,
+
+    /// Never aged out, multicast entries always are
+    #[arg(long = "static", default_value_t = false)]
+    is_static: bool,
+
+    /// Queue priority override of frames to the address
+    #[arg(long, default_value_t = 0, value_parser = clap::value_parser!(u8).range(0..8))]
+    qpri: u8,
+
+    /// Frame priority override of frames to the address
+    #[arg(long, default_value_t = 0, value_parser = clap::value_parser!(u8).range(0..8))]
+    fpri: u8,
+}
+
+#[derive(Args, Debug)]
+struct AtuDelArgs {
+    #[arg(long, default_value_t = 0, value_parser = clap::value_parser!(u16).range(0..4096))]
+    fid: u16,
+
+    /// Address of the entry
+    #[arg(long)]
+    mac: MacAddress,
+}
+
+#[derive(Args, Debug)]
+struct AtuFlushArgs {
+    /// Keep static entries
+    #[arg(long, default_value_t = false)]
+    non_static: bool,
+
+    /// Only entries of this fid, of every fid if omitted
+    #[arg(long, value_parser = clap::value_parser!(u16).range(0..4096))]
+    fid: Option<u16>,
+
+    /// Only this port, entries to other ports as well just lose it
+    #[arg(long)]
+    port: Option<u8>,
+}
+
+#[derive(Args, Debug)]
+struct AtuMoveArgs {
+    #[arg(long)]
+    from: u8,
+
+    #[arg(long)]
+    to: u8,
+
+    /// Leave static entries in place
+    #[arg(long, default_value_t = false)]
+    non_static: bool,
+
+    /// Only entries of this fid, of every fid if omitted
+    #[arg(long, value_parser = clap::value_parser!(u16).range(0..4096))]
+    fid: Option<u16>,
+}
+
+#[derive(Args, Debug)]
+struct AtuWatchArgs {
+    /// Time between walks, e.g. 500ms, 1s or 2m, seconds without unit
+    #[arg(long, default_value = "1s", value_parser = parse_interval)]
+    interval: Duration,
+
+    #[arg(long, default_value_t = 0, value_parser = clap::value_parser!(u16).range(0..4096))]
+    fid: u16,
+
+    /// Every fid instead of the one of --fid
+    #[arg(long, default_value_t = false, conflicts_with = "fid")]
+    all_fids: bool,
+
+    /// Stop after this many walks, run until killed if omitted
+    #[arg(long)]
+    count: Option<u32>,
+}
+
+fn parse_interval(text: &str) -> Result<Duration, String> {
+    let (num, unit) = text.split_at(text.find(|c: char| c.is_alphabetic()).unwrap_or(text.len()));
+    let num: f64 = num
+        .trim()
+        .parse()
+        .map_err(|_| format!("bad interval {}", text))?;
+    let secs = match unit {
+        "ms" => num / 1000.0,
+        "" | "s" => num,
+        "m" => num * 60.0,
+        _ => return Err(format!("bad interval unit {}, ms, s or m expected", unit)),
+    };
+    Duration::try_from_secs_f64(secs)
+        .ok()
+        .filter(|interval| !interval.is_zero())
+        .ok_or_else(|| format!("bad interval {}", text))
+}
+
+/// One ATU entry as the AtuOperation, AtuData, AtuFid and AtuMac registers hold it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct AtuEntry {
+    pub fid: u16,
+    pub mac: [u8; 6],
+    pub state: u8,
+    pub portvec: u16,
+    pub qpri: u8,
+    pub fpri: u8,
+}
+
+impl AtuEntry {
+    /// Never aged out, the multicast and the unicast states from 8 on
+    pub fn is_static(&self) -> bool {
+        self.mac[0] & 0x01 != 0 || self.state >= 0x8
+    }
+
+    /// The ports of the port vector, e.g. 1,3
+    pub fn ports(&self) -> String {
+        let ports: Vec<String> = (0..16)
+            .filter(|port| self.portvec & 1 << port != 0)
+            .map(|port| port.to_string())
+            .collect();
+        if ports.is_empty() {
+            return String::from("-");
+        }
+        ports.join(",")
+    }
+}
+
+impl fmt::Display for AtuEntry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "fid:{} mac(H):{} entry_state(H):{:X} portvec(B):{:010b} qpri:{} fpri:{}",
+            self.fid,
+            MacAddress::new(self.mac),
+            self.state,
+            self.portvec,
+            self.qpri,
+            self.fpri
+        )
+    }
+}
+
+fn write_g1(oplist: &mut RegOpRequestList, reg: G1, data: u16) {
+    oplist.add_regop(RegOpRequest::Write {
+        addr: GLOBAL1_ADDR,
+        reg: reg as u8,
+        data,
+    });
+}
+
+fn wait_atu_ready(oplist: &mut RegOpRequestList) {
+    oplist.add_regop(RegOpRequest::WaitOnBit0 {
+        addr: GLOBAL1_ADDR,
+        reg: G1::AtuOperation as u8,
+        bit: 15,
+    });
+}
+
+fn write_atu_mac(oplist: &mut RegOpRequestList, mac: &[u8; 6]) {
+    write_g1(oplist, G1::AtuMac01, u16::from_be_bytes([mac[0], mac[1]]));
+    write_g1(oplist, G1::AtuMac23, u16::from_be_bytes([mac[2], mac[3]]));
+    write_g1(oplist, G1::AtuMac45, u16::from_be_bytes([mac[4], mac[5]]));
+}
+
+/// AtuOperation value starting `code`, the busy bit starts the operation
+fn atu_op(code: AtuOperationAtuOpValue) -> u16 {
+    let op = u16_set_bits(0, 1, AtuOperation::AtuBusy);
+    u16_set_bits(op, code as u16, AtuOperation::AtuOp)
+}
+
+/// Load `entry`, or purge it with entry state 0
+fn build_load_purge(entry: &AtuEntry) -> RegOpRequestList {
+    let mut oplist = RegOpRequestList::new();
+    wait_atu_ready(&mut oplist);
+    write_g1(&mut oplist, G1::AtuFid, entry.fid);
+    write_atu_mac(&mut oplist, &entry.mac);
+
+    let data = u16_set_bits(0, entry.state.into(), AtuData::EntryState);
+    let data = u16_set_bits(data, entry.portvec, AtuData::PortVec);
+    write_g1(&mut oplist, G1::AtuData, data);
+
+    let op = atu_op(AtuOperationAtuOpValue::LoadPurge);
+    let op = u16_set_bits(op, entry.qpri.into(), AtuOperation::MacQPri);
+    let op = u16_set_bits(op, entry.fpri.into(), AtuOperation::MacFPri);
+    write_g1(&mut oplist, G1::AtuOperation, op);
+    wait_atu_ready(&mut oplist);
+    oplist
+}
+
+/// Flush, or move with `ports` as (from, to), the entries of `fid` or every fid
+fn build_flush_move(
+    fid: Option<u16>,
+    non_static: bool,
+    ports: Option<(u8, u8)>,
+) -> RegOpRequestList {
+    let mut oplist = RegOpRequestList::new();
+    wait_atu_ready(&mut oplist);
+    if let Some(fid) = fid {
+        write_g1(&mut oplist, G1::AtuFid, fid);
+    }
+
+    let data = match ports {
+        Some((from, to)) => {
+            let data = u16_set_bits(0, STATE_FLUSH_MOVE_PORT.into(), AtuData::EntryState);
+            u16_set_bits(data, u16::from(to) << 4 | u16::from(from), AtuData::PortVec)
+        }
+        None => u16_set_bits(0, STATE_FLUSH_ALL.into(), AtuData::EntryState),
+    };
+    write_g1(&mut oplist, G1::AtuData, data);
+
+    let code = match (fid.is_some(), non_static) {
+        (false, false) => AtuOperationAtuOpValue::FlushMoveAll,
+        (false, true) => AtuOperationAtuOpValue::FlushMoveNonStatic,
+        (true, false) => AtuOperationAtuOpValue::FlushMoveAllFid,
+        (true, true) => AtuOperationAtuOpValue::FlushMoveNonStaticFid,
+    };
+    write_g1(&mut oplist, G1::AtuOperation, atu_op(code));
+    wait_atu_ready(&mut oplist);
+    oplist
+}
+
+/// GetNext and the reads of its result, `GET_NEXT_OPS` ops
+fn add_get_next(oplist: &mut RegOpRequestList) {
+    write_g1(
+        oplist,
+        G1::AtuOperation,
+        atu_op(AtuOperationAtuOpValue::GetNext),
+    );
+    wait_atu_ready(oplist);
+    for reg in [
+        G1::AtuOperation,
+        G1::AtuFid,
+        G1::AtuData,
+        G1::AtuMac01,
+        G1::AtuMac23,
+        G1::AtuMac45,
+    ] {
+        oplist.add_regop(RegOpRequest::Read {
+            addr: GLOBAL1_ADDR,
+            reg: reg as u8,
+        });
+    }
+}
+
+const GET_NEXT_OPS: usize = 8;
+
+/// Highest fid the AtuFid register holds
+pub(super) const MAX_FID: u16 = 0x0FFF;
+
+/// GetNext from all ones starts at the lowest address
+pub(super) const MAC_ALL_ONES: [u8; 6] = [0xFF; 6];
+
+/// `add_get_next` from `mac` in `fid`
+fn add_seeded_get_next(oplist: &mut RegOpRequestList, fid: u16, mac: &[u8; 6]) {
+    wait_atu_ready(oplist);
+    write_g1(oplist, G1::AtuFid, fid);
+    write_atu_mac(oplist, mac);
+    add_get_next(oplist);
+}
+
+const SEED_OPS: usize = 5;
+const SEEDED_GET_NEXT_OPS: usize = SEED_OPS + GET_NEXT_OPS;
+
+/// Registers read back after a GetNext
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct AtuRegs {
+    pub op: u16,
+    pub fid: u16,
+    pub data: u16,
+    pub mac: [u8; 6],
+}
+
+impl AtuRegs {
+    /// The entry found, `None` at the end of the table
+    pub fn entry(&self) -> Option<AtuEntry> {
+        let state = u16_get_bits(self.data, AtuData::EntryState) as u8;
+        if state == STATE_PURGE {
+            return None;
+        }
+        Some(AtuEntry {
+            fid: u16_get_bits(self.fid, AtuFid::Fid),
+            mac: self.mac,
+            state,
+            portvec: u16_get_bits(self.data, AtuData::PortVec),
+            qpri: u16_get_bits(self.op, AtuOperation::MacQPri) as u8,
+            fpri: u16_get_bits(self.op, AtuOperation::MacFPri) as u8,
+        })
+    }
+}
+
+/// Registers out of the answers to `add_get_next`
+fn parse_get_next(regops: &[RegOpResponse]) -> anyhow::Result<AtuRegs> {
+    let mut data = [0u16; 6];
+    if regops.len() != GET_NEXT_OPS {
+        return Err(anyhow!("ATU GetNext not done: {:04x?}", regops));
+    }
+    for (val, op) in data.iter_mut().zip(&regops[2..]) {
+        match *op {
+            RegOpResponse::Read { data, .. } => *val = data,
+            _ => return Err(anyhow!("ATU GetNext not done: {:04x?}", regops)),
+        }
+    }
+    let [op, fid, data, mac01, mac23, mac45] = data;
+
+    let mut mac = [0u8; 6];
+    mac[0..2].copy_from_slice(&mac01.to_be_bytes());
+    mac[2..4].copy_from_slice(&mac23.to_be_bytes());
+    mac[4..6].copy_from_slice(&mac45.to_be_bytes());
+    Ok(AtuRegs { op, fid, data, mac })
+}
+
+/// The address before `mac`, GetNext from it finds `mac` first
+pub(super) fn mac_before(mac: &[u8; 6]) -> [u8; 6] {
+    let mut bytes = [0u8; 8];
+    bytes[2..].copy_from_slice(mac);
+    let prev = u64::from_be_bytes(bytes).wrapping_sub(1).to_be_bytes();
+    prev[2..].try_into().unwrap()
+}
+
+/// One GetNext from `from` in each of `fids`, the client packs them into as
+/// few frames as it can
+pub(super) async fn get_next_each<T: Transport>(
+    client: &RmuClient<T>,
+    dmac: &[u8; 6],
+    devid: u8,
+    fids: &[u16],
+    from: &[u8; 6],
+) -> anyhow::Result<Vec<AtuRegs>> {
+    let mut oplist = RegOpRequestList::new();
+    for &fid in fids {
+        add_seeded_get_next(&mut oplist, fid, from);
+    }
+    let reqlen = oplist.len();
+
+    let resp = client.register_ops_one(dmac, devid, oplist).await?;
+    if !resp.regops.ran_all(reqlen) {
+        return Err(anyhow!("ATU busy: {:04x?}", resp.regops));
+    }
+    resp.regops
+        .as_ref()
+        .chunks(SEEDED_GET_NEXT_OPS)
+        .map(|block| parse_get_next(&block[SEED_OPS..]))
+        .collect()
+}
+
+/// Entry of `mac` in `fid`, by a GetNext from the address before it
+pub(super) async fn lookup<T: Transport>(
+    client: &RmuClient<T>,
+    dmac: &[u8; 6],
+    devid: u8,
+    fid: u16,
+    mac: &[u8; 6],
+) -> anyhow::Result<Option<AtuEntry>> {
+    let regs = get_next_each(client, dmac, devid, &[fid], &mac_before(mac)).await?;
+    Ok(regs[0]
+        .entry()
+        .filter(|entry| entry.fid == fid && entry.mac == *mac))
+}
+
+/// The first GetNext of each of `fids`, `None` for an empty one
+async fn first_of_each<T: Transport>(
+    client: &RmuClient<T>,
+    dmac: &[u8; 6],
+    devid: u8,
+    fids: &[u16],
+) -> anyhow::Result<Vec<Option<AtuRegs>>> {
+    let firsts = get_next_each(client, dmac, devid, fids, &MAC_ALL_ONES).await?;
+    Ok(firsts
+        .into_iter()
+        .map(|regs| regs.entry().map(|_| regs))
+        .collect())
+}
+
+/// The entries of the fid of `first` from it on
+///
+/// Every frame carries as many GetNext as fit, the first seeded with the last
+/// address found. The answers count up to the first that is no entry or does
+/// not come after the one before, the walk wraps to the lowest address past
+/// the end.
+async fn walk_from<T: Transport>(
+    client: &RmuClient<T>,
+    dmac: &[u8; 6],
+    devid: u8,
+    first: AtuRegs,
+) -> anyhow::Result<Vec<AtuRegs>> {
+    let fid = u16_get_bits(first.fid, AtuFid::Fid);
+    let per_frame = (client.max_regops().saturating_sub(SEED_OPS) / GET_NEXT_OPS).max(1);
+    let mut found = vec![first];
+
+    loop {
+        let mut oplist = RegOpRequestList::new();
+        add_seeded_get_next(&mut oplist, fid, &found.last().unwrap().mac);
+        for _ in 1..per_frame {
+            add_get_next(&mut oplist);
+        }
+        let reqlen = oplist.len();
+        let resp = client.register_ops_one(dmac, devid, oplist).await?;
+        if !resp.regops.ran_all(reqlen) {
+            return Err(anyhow!("ATU busy: {:04x?}", resp.regops));
+        }
+
+        for block in resp.regops.as_ref()[SEED_OPS..].chunks(GET_NEXT_OPS) {
+            let regs = parse_get_next(block)?;
+            if regs.entry().is_none() || regs.mac <= found.last().unwrap().mac {
+                return Ok(found);
+            }
+            found.push(regs);
+        }
+    }
+}
+
+/// The entries of `fids` in fid and address order
+pub(super) async fn walk_fids<T: Transport>(
+    client: &RmuClient<T>,
+    dmac: &[u8; 6],
+    devid: u8,
+    fids: &[u16],
+) -> anyhow::Result<Vec<AtuRegs>> {
+    let mut found = Vec::new();
+    for first in first_of_each(client, dmac, devid, fids)
+        .await?
+        .into_iter()
+        .flatten()
+    {
+        found.extend(walk_from(client, dmac, devid, first).await?);
+    }
+    Ok(found)
+}
+
+/// Run an ATU operation, failing unless the ATU got done in time
+async fn run_atu_op<T: Transport>(
+    client: &RmuClient<T>,
+    dmac: &[u8; 6],
+    devid: u8,
+    oplist: RegOpRequestList,
+) -> anyhow::Result<()> {
+    let reqlen = oplist.len();
+    let resp = client.register_ops_one(dmac, devid, oplist).await?;
+    if !resp.regops.ran_all(reqlen) {
+        return Err(anyhow!("ATU busy: {:04x?}", resp.regops));
+    }
+    Ok(())
+}
+
+/// Refuse ports `chip` does not have
+fn check_ports(chip: Option<Chip>, ports: &[u8]) -> anyhow::Result<()> {
+    for &port in ports {
+        match chip {
+            Some(chip) => chip.check_port(port).map_err(anyhow::Error::msg)?,
+            None if port >= DEFAULT_PORT_COUNT => return Err(anyhow!("no port {}", port)),
+            None => (),
+        }
+    }
+    Ok(())
+}
+
+/// `in fid F` or nothing for every fid
+fn in_fid(fid: Option<u16>) -> String {
+    fid.map(|fid| format!(" in fid {}", fid))
+        .unwrap_or_default()
+}
+
+async fn add<T: Transport>(
+    cmd: &AtuCmd,
+    args: &AtuAddArgs,
+    client: &RmuClient<T>,
+) -> anyhow::Result<()> {
+    let dmac = cmd.dest.parse::<MacAddress>()?.bytes();
+    let chip = detect_chip(client, &dmac, cmd.devid).await?;
+
+    check_ports(chip, &args.ports)?;
+    let portvec = args.ports.iter().fold(0u16, |vec, port| vec | 1 << port);
+
+    let mac = args.mac.bytes();
+    let state = match (mac[0] & 0x01 != 0, args.is_static) {
+        (true, _) => STATE_MC_STATIC,
+        (false, true) => STATE_UC_STATIC,
+        (false, false) => STATE_UC_DYNAMIC,
+    };
+    let entry = AtuEntry {
+        fid: args.fid,
+        mac,
+        state,
+        portvec,
+        qpri: args.qpri,
+        fpri: args.fpri,
+    };
+    run_atu_op(client, &dmac, cmd.devid, build_load_purge(&entry)).await?;
+
+    match lookup(client, &dmac, cmd.devid, args.fid, &mac).await? {
+        Some(found) if found == entry => {
+            println!("loaded {}", found);
+            Ok(())
+        }
+        Some(found) => Err(anyhow!("loaded {}, but ATU holds {}", entry, found)),
+        None => Err(anyhow!("loaded {}, but ATU has no such entry", entry)),
+    }
+}
+
+async fn del<T: Transport>(
+    cmd: &AtuCmd,
+    args: &AtuDelArgs,
+    client: &RmuClient<T>,
+) -> anyhow::Result<()> {
+    let dmac = cmd.dest.parse::<MacAddress>()?.bytes();
+    let entry = AtuEntry {
+        fid: args.fid,
+        mac: args.mac.bytes(),
+        state: STATE_PURGE,
+        portvec: 0,
+        qpri: 0,
+        fpri: 0,
+    };
+    run_atu_op(client, &dmac, cmd.devid, build_load_purge(&entry)).await?;
+
+    match lookup(client, &dmac, cmd.devid, args.fid, &entry.mac).await? {
+        Some(found) => Err(anyhow!("purged, but ATU still holds {}", found)),
+        None => {
+            println!("purged fid:{} mac(H):{}", args.fid, args.mac);
+            Ok(())
+        }
+    }
+}
+
+async fn flush<T: Transport>(
+    cmd: &AtuCmd,
+    args: &AtuFlushArgs,
+    client: &RmuClient<T>,
+) -> anyhow::Result<()> {
+    let dmac = cmd.dest.parse::<MacAddress>()?.bytes();
+    if let Some(port) = args.port {
+        let chip = detect_chip(client, &dmac, cmd.devid).await?;
+        check_ports(chip, &[port])?;
+    }
+
+    let ports = args.port.map(|port| (port, PORT_NONE));
+    let oplist = build_flush_move(args.fid, args.non_static, ports);
+    run_atu_op(client, &dmac, cmd.devid, oplist).await?;
+
+    println!(
+        "flushed {}entries{}{}",
+        if args.non_static { "non-static " } else { "" },
+        args.port
+            .map(|port| format!(" of port {}", port))
+            .unwrap_or_default(),
+        in_fid(args.fid)
+    );
+    Ok(())
+}
+
+async fn move_port<T: Transport>(
+    cmd: &AtuCmd,
+    args: &AtuMoveArgs,
+    client: &RmuClient<T>,
+) -> anyhow::Result<()> {
+    let dmac = cmd.dest.parse::<MacAddress>()?.bytes();
+    if args.from == args.to {
+        return Err(anyhow!("entries of port {} are there already", args.to));
+    }
+    let chip = detect_chip(client, &dmac, cmd.devid).await?;
+    check_ports(chip, &[args.from, args.to])?;
+
+    let oplist = build_flush_move(args.fid, args.non_static, Some((args.from, args.to)));
+    run_atu_op(client, &dmac, cmd.devid, oplist).await?;
+
+    println!(
+        "moved {}entries of port {} to port {}{}",
+        if args.non_static { "non-static " } else { "" },
+        args.from,
+        args.to,
+        in_fid(args.fid)
+    );
+    Ok(())
+}
+
+/// Changes from `old` to `new` in the style of `bridge monitor fdb`
+fn watch_events(
+    old: &BTreeMap<(u16, [u8; 6]), AtuEntry>,
+    new: &BTreeMap<(u16, [u8; 6]), AtuEntry>,
+) -> Vec<String> {
+    let mut events = Vec::new();
+    for (key, entry) in new {
+        let mac = MacAddress::new(entry.mac);
+        match old.get(key) {
+            None => events.push(format!(
+                "{} fid {} ports {}{}",
+                mac,
+                entry.fid,
+                entry.ports(),
+                if entry.is_static() { " static" } else { "" }
+            )),
+            Some(was) if was.portvec != entry.portvec => events.push(format!(
+                "Moved {} fid {} ports {} -> {}",
+                mac,
+                entry.fid,
+                was.ports(),
+                entry.ports()
+            )),
+            Some(_) => (),
+        }
+    }
+    for (key, was) in old {
+        if !new.contains_key(key) {
+            events.push(format!(
+                "Deleted {} fid {} ports {}",
+                MacAddress::new(was.mac),
+                was.fid,
+                was.ports()
+            ));
+        }
+    }
+    events
+}
+
+async fn watch<T: Transport>(
+    cmd: &AtuCmd,
+    args: &AtuWatchArgs,
+    client: &RmuClient<T>,
+) -> anyhow::Result<()> {
+    let dmac = cmd.dest.parse::<MacAddress>()?.bytes();
+    let fids: Vec<u16> = if args.all_fids {
+        (0..=MAX_FID).collect()
+    } else {
+        vec![args.fid]
+    };
+
+    let start = Instant::now();
+    let mut known: Option<BTreeMap<(u16, [u8; 6]), AtuEntry>> = None;
+    let mut walks = 0;
+    loop {
+        let now = start.elapsed();
+        match walk_fids(client, &dmac, cmd.devid, &fids).await {
+            Ok(found) => {
+                let table: BTreeMap<(u16, [u8; 6]), AtuEntry> = found
+                    .iter()
+                    .filter_map(AtuRegs::entry)
+                    .map(|entry| ((entry.fid, entry.mac), entry))
+                    .collect();
+                // the first walk is what is there already
+                match &known {
+                    Some(known) => {
+                        for event in watch_events(known, &table) {
+                            println!("+{}.{:06} {}", now.as_secs(), now.subsec_micros(), event);
+                        }
+                    }
+                    None => println!("watching {} entries", table.len()),
+                }
+                known = Some(table);
+            }
+            Err(e) => eprintln!(
+                "+{}.{:06} walk failed: {}",
+                now.as_secs(),
+                now.subsec_micros(),
+                e
+            ),
+        }
+
+        walks += 1;
+        if args.count.is_some_and(|count| walks >= count) {
+            return Ok(());
+        }
+        smol::Timer::at(start + args.interval * walks).await;
+    }
+}
+
+async fn proccmd<T: Transport>(cmd: &AtuCmd, client: &RmuClient<T>) -> anyhow::Result<()> {
+    match &cmd.op {
+        AtuOp::Add(args) => add(cmd, args, client).await,
+        AtuOp::Del(args) => del(cmd, args, client).await,
+        AtuOp::Flush(args) => flush(cmd, args, client).await,
+        AtuOp::Move(args) => move_port(cmd, args, client).await,
+        AtuOp::Watch(args) => watch(cmd, args, client).await,
+    }
+}
+
+impl CommandOperation for AtuCmd {
+    fn process(&self) -> anyhow::Result<()> {
+        let client = open_client(&self.interface, self.timeout_ms)?;
+        smol::block_on(proccmd(self, &client))
+    }
+}
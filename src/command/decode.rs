@@ -0,0 +1,84 @@
+use std::io::Read;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::Context;
+use clap::Args;
+
+use mrmu::capture::{self, CapturedFrame, Direction};
+use mrmu::decode;
+
+use super::CommandOperation;
+
+/// Decode the RMU frames of a capture offline
+///
+/// Takes pcap, pcapng (e.g. from --capture) or a hex dump as printed by
+/// tcpdump -xx, xxd, hexdump -C or wireshark, one frame per block of lines.
+#[derive(Args, Debug)]
+pub struct DecodeCmd {
+    /// Capture file, - for stdin
+    file: PathBuf,
+
+    /// Print frames of other ethertypes too
+    #[arg(long, default_value_t = false)]
+    all: bool,
+}
+
+fn read_input(file: &PathBuf) -> anyhow::Result<Vec<u8>> {
+    if file.as_os_str() == "-" {
+        let mut data = Vec::new();
+        std::io::stdin().read_to_end(&mut data)?;
+        return Ok(data);
+    }
+    std::fs::read(file).with_context(|| format!("read {}", file.display()))
+}
+
+fn frame_prefix(idx: usize, frame: &CapturedFrame, first_ts: Option<Duration>) -> String {
+    let mut prefix = format!("#{}", idx + 1);
+    if let (Some(ts), Some(first)) = (frame.timestamp, first_ts) {
+        let rel = ts.saturating_sub(first);
+        prefix += &format!(" +{}.{:06}", rel.as_secs(), rel.subsec_micros());
+    }
+    match frame.direction {
+        Some(Direction::Outbound) => prefix += " out",
+        Some(Direction::Inbound) => prefix += " in ",
+        None => (),
+    }
+    prefix
+}
+
+impl CommandOperation for DecodeCmd {
+    fn process(&self) -> anyhow::Result<()> {
+        let data = read_input(&self.file)?;
+        let frames = if capture::is_capture(&data) {
+            capture::read_capture(&data)?
+        } else {
+            let text = String::from_utf8(data).context("neither capture nor hex dump")?;
+            capture::parse_hexdump(&text)?
+        };
+
+        let first_ts = frames.first().and_then(|f| f.timestamp);
+        for (idx, frame) in frames.iter().enumerate() {
+            let prefix = frame_prefix(idx, frame, first_ts);
+            match decode::decode_frame(&frame.data) {
+                Ok(Some(text)) => println!("{} {}", prefix, text),
+                Ok(None) if self.all => {
+                    let ethertype = match frame.data.get(12..14) {
+                        Some(b) => format!("0x{:02X}{:02X}", b[0], b[1]),
+                        None => "runt".to_string(),
+                    };
+                    println!(
+                        "{} ethertype {} len:{}",
+                        prefix,
+                        ethertype,
+                        frame.data.len()
+                    );
+                }
+                Ok(None) => (),
+                Err(e) => println!("{} malformed rmu frame: {}", prefix, e),
+            }
+        }
+
+        Ok(())
+    }
+}
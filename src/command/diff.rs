@@ -0,0 +1,47 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use clap::Args;
+
+use mrmu::reginfo::register_db;
+use mrmu::snapshot::{self, Snapshot};
+
+use super::CommandOperation;
+
+/// Field level differences between two snapshots of `dump`
+#[derive(Args, Debug)]
+pub struct DiffCmd {
+    old: PathBuf,
+
+    new: PathBuf,
+}
+
+fn load(path: &Path) -> anyhow::Result<Snapshot> {
+    Snapshot::load(path).with_context(|| format!("read {}", path.display()))
+}
+
+impl CommandOperation for DiffCmd {
+    fn process(&self) -> anyhow::Result<()> {
+        let old = load(&self.old)?;
+        let new = load(&self.new)?;
+
+        if old.devid != new.devid {
+            println!("devid 0x{:02X} -> 0x{:02X}", old.devid, new.devid);
+        }
+        if old.product_number != new.product_number {
+            println!(
+                "prodno 0x{:04X} -> 0x{:04X}",
+                old.product_number, new.product_number
+            );
+        }
+
+        let changes = snapshot::diff(&old, &new, register_db());
+        if changes.is_empty() {
+            println!("no register differs");
+        }
+        for change in changes {
+            println!("{}", change);
+        }
+        Ok(())
+    }
+}
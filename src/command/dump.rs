@@ -0,0 +1,99 @@
+use std::path::PathBuf;
+
+use anyhow::Context;
+use clap::Args;
+use mac_address::MacAddress;
+
+use mrmu::chip::DEFAULT_PORT_COUNT;
+use mrmu::message::MessageOperation;
+use mrmu::reginfo::{register_db, Device, GLOBAL1_ADDR, GLOBAL2_ADDR};
+use mrmu::snapshot::{RegisterValue, Snapshot};
+use mrmu::transport::Transport;
+use mrmu::RmuClient;
+
+use super::{chip_of, open_client, read_block, CommandOperation};
+
+/// Save every described register of all ports, Global1 and Global2 as JSON
+///
+/// One frame per port or global block. Compare two dumps with `diff`.
+/// Indirect tables are left out, see `RegisterDesc::is_indirect`.
+#[derive(Args, Debug)]
+pub struct DumpCmd {
+    #[arg(short, long)]
+    interface: String,
+
+    #[arg(short, long, default_value_t = 100)]
+    timeout_ms: u32,
+
+    #[arg(short, long)]
+    #[arg(default_value_t = String::from("01:50:43:00:00:03"))]
+    mac: String,
+
+    #[arg(short, long, value_parser=clap_num::maybe_hex::<u8>)]
+    devid: u8,
+
+    /// Snapshot file to write
+    #[arg(short, long)]
+    output: PathBuf,
+}
+
+async fn proccmd<T: Transport>(cmd: &DumpCmd, client: &RmuClient<T>) -> anyhow::Result<()> {
+    let dmac = cmd.mac.parse::<MacAddress>()?.bytes();
+    let prodno = client
+        .get_id_one(&dmac, cmd.devid)
+        .await?
+        .header()
+        .product_number();
+    let chip = chip_of(prodno);
+    let port_count = chip.map_or(DEFAULT_PORT_COUNT, |chip| chip.port_count());
+
+    let mut blocks: Vec<(u8, Device)> = (0..port_count).map(|port| (port, Device::Port)).collect();
+    blocks.push((GLOBAL1_ADDR, Device::Global1));
+    blocks.push((GLOBAL2_ADDR, Device::Global2));
+
+    let mut snapshot = Snapshot {
+        devid: cmd.devid,
+        product_number: prodno,
+        registers: Vec::new(),
+    };
+    for (addr, device) in blocks {
+        let descs: Vec<_> = register_db()
+            .registers(device)
+            .into_iter()
+            .filter(|desc| !desc.is_indirect())
+            .filter(|desc| match (chip, desc.feature) {
+                (Some(chip), Some(feature)) => chip.has(feature),
+                _ => true,
+            })
+            .collect();
+
+        let regs: Vec<u8> = descs.iter().map(|desc| desc.offset).collect();
+        let values = read_block(client, &dmac, cmd.devid, addr, &regs).await?;
+        for (desc, value) in descs.iter().zip(values) {
+            snapshot.registers.push(RegisterValue {
+                addr,
+                reg: desc.offset,
+                name: desc.name.clone(),
+                value,
+            });
+        }
+    }
+
+    snapshot
+        .save(&cmd.output)
+        .with_context(|| format!("write {}", cmd.output.display()))?;
+    println!(
+        "{} registers of devid 0x{:02X} to {}",
+        snapshot.registers.len(),
+        cmd.devid,
+        cmd.output.display()
+    );
+    Ok(())
+}
+
+impl CommandOperation for DumpCmd {
+    fn process(&self) -> anyhow::Result<()> {
+        let client = open_client(&self.interface, self.timeout_ms)?;
+        smol::block_on(proccmd(self, &client))
+    }
+}
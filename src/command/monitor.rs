@@ -0,0 +1,98 @@
+use std::io::ErrorKind;
+use std::time::{Duration, Instant};
+
+use clap::Args;
+
+use mrmu::capture::Direction;
+use mrmu::decode::{self, Correlator};
+use mrmu::packet_sock::Sniffer;
+
+use super::CommandOperation;
+
+/// Decode the RMU traffic on an interface as it passes
+///
+/// The interface is put in promiscuous mode, so requests of other tools and
+/// traffic of the switch firmware show up too. Responses are paired with their
+/// request and the latency printed.
+#[derive(Args, Debug)]
+pub struct MonitorCmd {
+    #[arg(short, long)]
+    interface: String,
+
+    /// How long a request waits for responses before it counts as unanswered
+    #[arg(short, long, default_value_t = 1000)]
+    window_ms: u32,
+}
+
+async fn proccmd(cmd: &MonitorCmd, sniffer: &Sniffer) -> anyhow::Result<()> {
+    let start = Instant::now();
+    let mut correlator = Correlator::new(Duration::from_millis(cmd.window_ms.into()));
+    let mut count = 0;
+
+    println!("monitor {}", cmd.interface);
+    loop {
+        let mut rbuf = [0; 1514];
+        let deadline = Instant::now() + Duration::from_millis(100);
+        let recvd = sniffer.recv(&mut rbuf, deadline).await;
+        let now = start.elapsed();
+
+        for id in correlator.expire(now) {
+            println!("#{} no response within {}ms", id, cmd.window_ms);
+        }
+
+        let (sz, direction) = match recvd {
+            Ok(recvd) => recvd,
+            Err(e) if e.kind() == ErrorKind::TimedOut => continue,
+            Err(e) => return Err(e.into()),
+        };
+        let frame = &rbuf[..sz];
+        if !decode::is_rmu_frame(frame) {
+            continue;
+        }
+
+        count += 1;
+        let dir = match direction {
+            Direction::Outbound => "out",
+            Direction::Inbound => "in ",
+        };
+        let prefix = format!(
+            "#{} +{}.{:06} {}",
+            count,
+            now.as_secs(),
+            now.subsec_micros(),
+            dir
+        );
+
+        let text = match decode::decode_frame(frame) {
+            Ok(Some(text)) => text,
+            Ok(None) => continue,
+            Err(e) => {
+                println!("{} malformed rmu frame: {}", prefix, e);
+                continue;
+            }
+        };
+
+        // the pairing goes at the end of the summary line
+        let (summary, details) = text.split_once('\n').unwrap_or((&text, ""));
+        match correlator.observe(count, frame, now) {
+            Some(corr) => println!(
+                "{} {} [reply to #{} in {:.3}ms]",
+                prefix,
+                summary,
+                corr.request,
+                corr.latency.as_secs_f64() * 1000.0
+            ),
+            None => println!("{} {}", prefix, summary),
+        }
+        if !details.is_empty() {
+            println!("{}", details);
+        }
+    }
+}
+
+impl CommandOperation for MonitorCmd {
+    fn process(&self) -> anyhow::Result<()> {
+        let sniffer = Sniffer::open(&self.interface)?;
+        smol::block_on(proccmd(self, &sniffer))
+    }
+}
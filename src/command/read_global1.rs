@@ -0,0 +1,65 @@
+use clap::Args;
+use mac_address::MacAddress;
+
+use mrmu::reginfo::{register_db, Device, GLOBAL1_ADDR};
+use mrmu::transport::Transport;
+use mrmu::RmuClient;
+
+use super::{check_register, detect_chip, open_client, read_and_print, CommandOperation};
+
+/// Read and decode Global1 registers (SMI address 0x1B)
+#[derive(Args, Debug)]
+pub struct ReadGlobal1Cmd {
+    #[arg(short, long)]
+    interface: String,
+
+    #[arg(short, long, default_value_t = 100)]
+    timeout_ms: u32,
+
+    #[arg(short, long)]
+    #[arg(default_value_t = String::from("01:50:43:00:00:03"))]
+    mac: String,
+
+    #[arg(short, long, value_parser=clap_num::maybe_hex::<u8>)]
+    devid: u8,
+
+    /// Register name of the description, e.g. atu_operation
+    #[arg(short, long)]
+    register: String,
+
+    /// Comma separated fields to print, all of the register if omitted
+    #[arg(short, long, num_args = 1, value_delimiter = ',')]
+    fields: Option<Vec<String>>,
+
+    /// Print raw register value too
+    #[arg(long)]
+    #[arg(default_value_t = false)]
+    print_reg: bool,
+}
+
+async fn proccmd<T: Transport>(cmd: &ReadGlobal1Cmd, client: &RmuClient<T>) -> anyhow::Result<()> {
+    let dmac = cmd.mac.parse::<MacAddress>()?.bytes();
+    let desc = register_db()
+        .find(Device::Global1, &cmd.register)
+        .map_err(anyhow::Error::msg)?;
+    let chip = detect_chip(client, &dmac, cmd.devid).await?;
+    check_register(chip, desc, None)?;
+
+    read_and_print(
+        client,
+        &dmac,
+        cmd.devid,
+        GLOBAL1_ADDR,
+        desc,
+        &cmd.fields,
+        cmd.print_reg,
+    )
+    .await
+}
+
+impl CommandOperation for ReadGlobal1Cmd {
+    fn process(&self) -> anyhow::Result<()> {
+        let client = open_client(&self.interface, self.timeout_ms)?;
+        smol::block_on(proccmd(self, &client))
+    }
+}
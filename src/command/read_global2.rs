@@ -0,0 +1,99 @@
+use anyhow::anyhow;
+use clap::Args;
+use mac_address::MacAddress;
+
+use mrmu::message::register::{RegOpRequest, RegOpRequestList};
+use mrmu::reginfo::{register_db, Device, Field, GLOBAL2_ADDR};
+use mrmu::transport::Transport;
+use mrmu::RmuClient;
+
+use super::{
+    check_register, detect_chip, open_client, read_and_print, set_fields, CommandOperation,
+};
+
+/// Read and decode Global2 registers (SMI address 0x1C)
+///
+/// Registers fronting an indirect table take --index to select the table
+/// entry before the read, see `RegisterDesc::is_indirect`.
+#[derive(Args, Debug)]
+pub struct ReadGlobal2Cmd {
+    #[arg(short, long)]
+    interface: String,
+
+    #[arg(short, long, default_value_t = 100)]
+    timeout_ms: u32,
+
+    #[arg(short, long)]
+    #[arg(default_value_t = String::from("01:50:43:00:00:03"))]
+    mac: String,
+
+    #[arg(short, long, value_parser=clap_num::maybe_hex::<u8>)]
+    devid: u8,
+
+    /// Register name of the description, e.g. smi_phy_command
+    #[arg(short, long)]
+    register: String,
+
+    /// Table entry to read of an indirect register, comma separated
+    /// field=value of its index fields, e.g. fpri_set=1,pointer=3. Index
+    /// fields left out are 0
+    #[arg(long, num_args = 1, value_delimiter = ',')]
+    index: Option<Vec<String>>,
+
+    /// Comma separated fields to print, all of the register if omitted
+    #[arg(short, long, num_args = 1, value_delimiter = ',')]
+    fields: Option<Vec<String>>,
+
+    /// Print raw register value too
+    #[arg(long)]
+    #[arg(default_value_t = false)]
+    print_reg: bool,
+}
+
+async fn proccmd<T: Transport>(cmd: &ReadGlobal2Cmd, client: &RmuClient<T>) -> anyhow::Result<()> {
+    let dmac = cmd.mac.parse::<MacAddress>()?.bytes();
+
+    let desc = register_db()
+        .find(Device::Global2, &cmd.register)
+        .map_err(anyhow::Error::msg)?;
+    let chip = detect_chip(client, &dmac, cmd.devid).await?;
+    check_register(chip, desc, None)?;
+
+    if let Some(index) = &cmd.index {
+        if !desc.is_indirect() {
+            return Err(anyhow!("{} is no indirect table", desc.name));
+        }
+        let index_fields: Vec<Field> = desc
+            .fields
+            .iter()
+            .filter(|field| desc.index.contains(&field.name))
+            .cloned()
+            .collect();
+        // update bit clear, only moves the index
+        let mut regops = RegOpRequestList::new();
+        regops.add_regop(RegOpRequest::Write {
+            addr: GLOBAL2_ADDR,
+            reg: desc.offset,
+            data: set_fields(&desc.name, 0, &index_fields, index)?,
+        });
+        client.register_ops_one(&dmac, cmd.devid, regops).await?;
+    }
+
+    read_and_print(
+        client,
+        &dmac,
+        cmd.devid,
+        GLOBAL2_ADDR,
+        desc,
+        &cmd.fields,
+        cmd.print_reg,
+    )
+    .await
+}
+
+impl CommandOperation for ReadGlobal2Cmd {
+    fn process(&self) -> anyhow::Result<()> {
+        let client = open_client(&self.interface, self.timeout_ms)?;
+        smol::block_on(proccmd(self, &client))
+    }
+}
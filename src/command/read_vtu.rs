@@ -1,20 +1,19 @@
-use std::io::{self, Read, Write};
-use std::io::ErrorKind;
+use std::io::{self, ErrorKind};
 
 use anyhow::anyhow;
-use bit_ops::bitops_u16;
 use clap::Args;
-use mac_address::mac_address_by_name;
 use mac_address::MacAddress;
 
-use crate::message::header::RequestHeader;
-use crate::message::register::{RegOpRequest, RegOpRequestList, RegOpResponse};
-use crate::message::register::{RegisterRequest, RegisterResponse};
-use crate::message::{self, MessageOperation};
-use crate::message_builder::MessageBuilder;
-use crate::packet_sock;
+use mrmu::message::register::{RegOpRequest, RegOpRequestList, RegOpResponse};
+use mrmu::reginfo::{u16_get_bits, VtuFid, VtuVid};
+use mrmu::reginfo::{Global1Register as G1, VtuOperationVtuOpValue, GLOBAL1_ADDR};
+use mrmu::transport::Transport;
+use mrmu::RmuClient;
 
-use super::CommandOperation;
+use super::{open_client, CommandOperation};
+
+// busy bit starts the operation
+const OP_GET_NEXT: u16 = 0x8000 | (VtuOperationVtuOpValue::GetNext as u16) << 12;
 
 #[derive(Args, Debug)]
 pub struct ReadVtuCmd {
@@ -39,14 +38,14 @@ fn build_prepare_requests() -> RegOpRequestList {
     let mut oplist = RegOpRequestList::new();
 
     oplist.add_regop(RegOpRequest::WaitOnBit0 {
-        addr: 0x1B,
-        reg: 0x05,
+        addr: GLOBAL1_ADDR,
+        reg: G1::VtuOperation as u8,
         bit: 15,
     });
 
     oplist.add_regop(RegOpRequest::Write {
-        addr: 0x1B,
-        reg: 0x06,
+        addr: GLOBAL1_ADDR,
+        reg: G1::VtuVid as u8,
         data: 0x2FFF,
     });
 
@@ -57,94 +56,54 @@ fn build_requests() -> RegOpRequestList {
     let mut oplist = RegOpRequestList::new();
 
     oplist.add_regop(RegOpRequest::Write {
-        addr: 0x1B,
-        reg: 0x05,
-        data: 0xC000,
+        addr: GLOBAL1_ADDR,
+        reg: G1::VtuOperation as u8,
+        data: OP_GET_NEXT,
     });
 
     oplist.add_regop(RegOpRequest::WaitOnBit0 {
-        addr: 0x1B,
-        reg: 0x05,
+        addr: GLOBAL1_ADDR,
+        reg: G1::VtuOperation as u8,
         bit: 15,
     });
 
     // VTU FID
     oplist.add_regop(RegOpRequest::Read {
-        addr: 0x1B,
-        reg: 0x02,
+        addr: GLOBAL1_ADDR,
+        reg: G1::VtuFid as u8,
     });
     // VTU_VID
     oplist.add_regop(RegOpRequest::Read {
-        addr: 0x1B,
-        reg: 0x06,
+        addr: GLOBAL1_ADDR,
+        reg: G1::VtuVid as u8,
     });
 
     oplist.add_regop(RegOpRequest::Read {
-        addr: 0x1B,
-        reg: 0x07,
+        addr: GLOBAL1_ADDR,
+        reg: G1::VtuDataP0p7 as u8,
     });
 
     oplist.add_regop(RegOpRequest::Read {
-        addr: 0x1B,
-        reg: 0x08,
+        addr: GLOBAL1_ADDR,
+        reg: G1::VtuDataP8p9 as u8,
     });
     oplist
 }
 
-async fn proccmd(cmd: &ReadVtuCmd) -> anyhow::Result<()> {
-    let sock = packet_sock::create_rmu_sock(&cmd.interface)?;
-    let smac = mac_address_by_name(&cmd.interface)?.unwrap().bytes();
+async fn proccmd<T: Transport>(cmd: &ReadVtuCmd, client: &RmuClient<T>) -> anyhow::Result<()> {
     let dmac = cmd.mac.parse::<MacAddress>()?.bytes();
 
-    let mut requ = Into::<MessageBuilder<RegisterRequest>>::into(
-        MessageBuilder::<RequestHeader>::new()
-            .destination_address(&dmac)
-            .source_address(&smac)
-            .device_id(cmd.devid),
-    )
-    .regops(build_prepare_requests())
-    .build()?;
-
-    let mut wbuf = message::prealloc_buffer(&requ);
-    let _ = requ.marshal(&mut wbuf[..])?;
-    sock.write_with(|mut s| s.write(&wbuf[..])).await?;
-
-    let mut rbuf = [0; 1514];
-    let sz = sock.read_with(|mut s| s.read(&mut rbuf)).await?;
-    if sz == 0 {
-        return Err(io::Error::from(ErrorKind::UnexpectedEof).into());
-    }
-
     // @todo: how to check successful
-    let _resp = message::unmarshal::<RegisterResponse>(&rbuf[..sz])?;
-    // println!("{:04X?}", resp.regops);
+    let _resp = client
+        .register_ops_one(&dmac, cmd.devid, build_prepare_requests())
+        .await?;
 
     let mut first_vid = None;
-    let mut seqno = 1;
     loop {
-        let mut req = Into::<MessageBuilder<RegisterRequest>>::into(
-            MessageBuilder::<RequestHeader>::new()
-                .destination_address(&dmac)
-                .source_address(&smac)
-                .sequence_number(seqno)
-                .device_id(cmd.devid),
-        )
-        .regops(build_requests())
-        .build()?;
-
-        seqno += 1;
-
-        let mut wbuf = message::prealloc_buffer(&req);
-        let _ = req.marshal(&mut wbuf[..])?;
-        sock.write_with(|mut s| s.write(&wbuf[..])).await?;
-        let mut rbuf = [0; 1514];
-        let sz = sock.read_with(|mut s| s.read(&mut rbuf)).await?;
-        if sz == 0 {
-            return Err(io::Error::from(ErrorKind::UnexpectedEof).into());
-        }
-
-        let resp = message::unmarshal::<RegisterResponse>(&rbuf[..sz])?;
-        if req.regops.as_ref().len() != resp.regops.as_ref().len() {
+        let regops = build_requests();
+        let reqlen = regops.as_ref().len();
+        let resp = client.register_ops_one(&dmac, cmd.devid, regops).await?;
+        if reqlen != resp.regops.as_ref().len() {
             eprintln!("response with error: {:04x?}", resp.regops);
             return Err(io::Error::from(ErrorKind::InvalidData).into());
         }
@@ -158,9 +117,9 @@ async fn proccmd(cmd: &ReadVtuCmd) -> anyhow::Result<()> {
             _ => return Err(anyhow!("read vtu_vid fail")),
         };
 
-        let vid = bitops_u16::get_bits(vtu_vid, 12, 0);
-        let valid = bitops_u16::get_bit(vtu_vid, 12);
-        let page = bitops_u16::get_bit(vtu_vid, 13);
+        let vid = u16_get_bits(vtu_vid, VtuVid::Vid);
+        let valid = u16_get_bits(vtu_vid, VtuVid::Valid);
+        let page = u16_get_bits(vtu_vid, VtuVid::Page);
 
         if (valid == 0) || (first_vid.is_some_and(|x| x == vid)) {
             break;
@@ -174,7 +133,7 @@ async fn proccmd(cmd: &ReadVtuCmd) -> anyhow::Result<()> {
             RegOpResponse::Read { data, .. } => data,
             _ => return Err(anyhow!("read vtu_fid fail")),
         };
-        let fid = bitops_u16::get_bits(vtu_fid, 12, 0);
+        let fid = u16_get_bits(vtu_fid, VtuFid::Fid);
 
         println!("fid:{} vid(H):{:04X} page:{}", fid, vid, page);
 
@@ -194,7 +153,7 @@ async fn proccmd(cmd: &ReadVtuCmd) -> anyhow::Result<()> {
                 " vtu_data_p0p7:{:04X} vtu_data_p8p9:{:04X}",
                 vtu_data_p0p7, vtu_data_p8p9
             );
-            println!("");
+            println!();
         }
     }
 
@@ -203,6 +162,7 @@ async fn proccmd(cmd: &ReadVtuCmd) -> anyhow::Result<()> {
 
 impl CommandOperation for ReadVtuCmd {
     fn process(&self) -> anyhow::Result<()> {
-        smol::block_on(proccmd(self))
+        let client = open_client(&self.interface, self.timeout_ms)?;
+        smol::block_on(proccmd(self, &client))
     }
 }
@@ -1,23 +1,14 @@
-use std::io::{Read, Write};
-use std::{io::ErrorKind, time::Duration};
-
 use clap::Args;
-use mac_address::mac_address_by_name;
 use mac_address::MacAddress;
-use smol::future::FutureExt;
-use smol::Timer;
 
-use crate::message;
-use crate::message::header::RequestHeader;
-use crate::message::register::RegOpRequest;
-use crate::message::register::RegOpRequestList;
-use crate::message::register::RegisterRequest;
-use crate::message::register::RegisterResponse;
-use crate::message::MessageOperation;
-use crate::message_builder::MessageBuilder;
-use crate::packet_sock;
+use mrmu::message::register::RegOpRequest;
+use mrmu::message::register::RegOpRequestList;
+use mrmu::message::MessageOperation;
+use mrmu::reginfo::RegisterPath;
+use mrmu::transport::Transport;
+use mrmu::RmuClient;
 
-use super::CommandOperation;
+use super::{open_client, CommandOperation};
 
 /// Perform register opeartions
 #[derive(Args, Debug)]
@@ -39,7 +30,11 @@ pub struct RegOpCmd {
     ///
     /// Support register operations:
     ///   READ:addr=[],reg=[] | WRITE:addr=[],reg=[],data=[] |
-    ///   WaitOnBit0:addr=[],reg=[],bit=[] | WaitOnBit1:addr=[],reg=[],bit=[]
+    ///   WaitBit0:addr=[],reg=[],bit=[] | WaitBit1:addr=[],reg=[],bit=[]
+    ///
+    /// addr and reg can be given by name instead, e.g. READ:port3.port_status,
+    /// WRITE:global1.atu_fid,data=0x10 or WaitBit0:global1.atu_op.atu_busy
+    /// where the field names the bit
     #[arg(short, long, num_args=1..)]
     actions: Vec<String>,
 }
@@ -49,7 +44,7 @@ fn strtoint<T: num_traits::Num>(val: &str) -> Option<T> {
         return T::from_str_radix(&val[2..], 16).ok();
     }
 
-    if val.starts_with("0o") || val.starts_with("0o") {
+    if val.starts_with("0o") || val.starts_with("0O") {
         return T::from_str_radix(&val[2..], 8).ok();
     }
 
@@ -57,7 +52,16 @@ fn strtoint<T: num_traits::Num>(val: &str) -> Option<T> {
         return T::from_str_radix(&val[2..], 2).ok();
     }
 
-    T::from_str_radix(&val[..], 10).ok()
+    T::from_str_radix(val, 10).ok()
+}
+
+fn parse_path(para: &str, last_addr: &mut Option<u8>) -> anyhow::Result<RegisterPath> {
+    let path = para
+        .trim()
+        .parse::<RegisterPath>()
+        .map_err(anyhow::Error::msg)?;
+    last_addr.replace(path.addr);
+    Ok(path)
 }
 
 fn parse_read(param: &str, last_addr: &mut Option<u8>) -> anyhow::Result<RegOpRequest> {
@@ -66,6 +70,15 @@ fn parse_read(param: &str, last_addr: &mut Option<u8>) -> anyhow::Result<RegOpRe
 
     let paras: Vec<_> = param.split(',').collect();
     for para in paras {
+        if !para.contains('=') {
+            let path = parse_path(para, last_addr)?;
+            if path.field.is_some() {
+                return Err(anyhow::anyhow!("{}: read takes a register", para));
+            }
+            (addr, reg) = (Some(path.addr), Some(path.reg));
+            continue;
+        }
+
         let kv: Vec<_> = para.split('=').collect();
         if kv.len() != 2 {
             return Err(anyhow::anyhow!("wrong read keyval pair"));
@@ -112,6 +125,18 @@ fn parse_write(param: &str, last_addr: &mut Option<u8>) -> anyhow::Result<RegOpR
 
     let paras: Vec<_> = param.split(',').collect();
     for para in paras {
+        if !para.contains('=') {
+            let path = parse_path(para, last_addr)?;
+            if path.field.is_some() {
+                return Err(anyhow::anyhow!(
+                    "{}: write takes a register, see write-port for fields",
+                    para
+                ));
+            }
+            (addr, reg) = (Some(path.addr), Some(path.reg));
+            continue;
+        }
+
         let kv: Vec<_> = para.split('=').collect();
         if kv.len() != 2 {
             return Err(anyhow::anyhow!("wrong read keyval pair"));
@@ -164,6 +189,18 @@ fn parse_waitbit(
 
     let paras: Vec<_> = param.split(',').collect();
     for para in paras {
+        if !para.contains('=') {
+            let path = parse_path(para, last_addr)?;
+            (addr, reg) = (Some(path.addr), Some(path.reg));
+            if let Some(field) = path.field {
+                if field.info.len != 1 {
+                    return Err(anyhow::anyhow!("{}: field is no single bit", para));
+                }
+                bit = Some(field.info.shift);
+            }
+            continue;
+        }
+
         let kv: Vec<_> = para.split('=').collect();
         if kv.len() != 2 {
             return Err(anyhow::anyhow!("wrong read keyval pair"));
@@ -213,7 +250,7 @@ fn parse_waitbit(
     })
 }
 
-fn parse_actions(actions: &Vec<String>) -> anyhow::Result<RegOpRequestList> {
+fn parse_actions(actions: &[String]) -> anyhow::Result<RegOpRequestList> {
     let mut oplist = RegOpRequestList::new();
     let mut last_addr: Option<u8> = None;
 
@@ -226,8 +263,12 @@ fn parse_actions(actions: &Vec<String>) -> anyhow::Result<RegOpRequestList> {
         match actpara[0].to_lowercase().as_str() {
             "read" => oplist.add_regop(parse_read(actpara[1], &mut last_addr)?),
             "write" => oplist.add_regop(parse_write(actpara[1], &mut last_addr)?),
-            "waitbit0" => oplist.add_regop(parse_waitbit(actpara[1], true, &mut last_addr)?),
-            "waitbit1" => oplist.add_regop(parse_waitbit(actpara[1], false, &mut last_addr)?),
+            "waitbit0" | "waitonbit0" => {
+                oplist.add_regop(parse_waitbit(actpara[1], true, &mut last_addr)?)
+            }
+            "waitbit1" | "waitonbit1" => {
+                oplist.add_regop(parse_waitbit(actpara[1], false, &mut last_addr)?)
+            }
             _ => return Err(anyhow::anyhow!("invalid op:{}", actpara[0])),
         }
     }
@@ -235,75 +276,39 @@ fn parse_actions(actions: &Vec<String>) -> anyhow::Result<RegOpRequestList> {
     Ok(oplist)
 }
 
-impl CommandOperation for RegOpCmd {
-    fn process(&self) -> anyhow::Result<()> {
-        let sock = packet_sock::create_rmu_sock(&self.interface)?;
-        let smac = mac_address_by_name(&self.interface)?.unwrap().bytes();
-        let dmac = self.mac.parse::<MacAddress>()?.bytes();
-
-        let oplist = parse_actions(&self.actions)?;
-
-        smol::block_on(async {
-            let mut requ = Into::<MessageBuilder<RegisterRequest>>::into(
-                MessageBuilder::<RequestHeader>::new()
-                    .destination_address(&dmac)
-                    .source_address(&smac)
-                    .device_id(self.devid),
-            )
-            .regops(oplist)
-            .build()?;
-
-            // @todo:
-            let mut wbuf = Vec::new();
-            let len = if requ.wire_size() > 60 {
-                requ.wire_size()
-            } else {
-                60
-            };
-
-            wbuf.resize(len, 0);
-            let _ = requ.marshal(&mut wbuf[..])?;
-            sock.write_with(|mut s| s.write(&wbuf[..])).await?;
-            let mut has_response = false;
-
-            loop {
-                let mut rbuf = [0; 1514];
-                let res = sock
-                    .read_with(|mut s| s.read(&mut rbuf))
-                    .or(async {
-                        Timer::after(Duration::from_millis(self.timeout_ms.into())).await;
-                        Err(ErrorKind::TimedOut.into())
-                    })
-                    .await;
-
-                match res {
-                    Ok(sz) if sz > 0 => {
-                        has_response = true;
-                        let resp: RegisterResponse = message::unmarshal(&rbuf[..sz])?;
-                        let mac: MacAddress = resp.header().source_address().into();
-                        println!(
-                            "Mac:{mac} Devid:0x{:02X}\n {:04X?}",
-                            resp.header().device_id(),
-                            resp.regops,
-                        );
-                    }
-                    Ok(_) => continue,
-                    Err(e) if e.kind() == ErrorKind::TimedOut => {
-                        if has_response {
-                            break;
-                        }
-
-                        return Err(anyhow::anyhow!(
-                            "No response: check network or no rmu at mac={},devid=0x{:02X}",
-                            self.mac,
-                            self.devid
-                        ));
-                    }
-                    Err(e) => return Err(e.into()),
-                }
+async fn proccmd<T: Transport>(cmd: &RegOpCmd, client: &RmuClient<T>) -> anyhow::Result<()> {
+    let dmac = cmd.mac.parse::<MacAddress>()?.bytes();
+    let oplist = parse_actions(&cmd.actions)?;
+
+    let replies = client.register_ops(&dmac, cmd.devid, oplist).await?;
+    if replies.is_empty() {
+        return Err(anyhow::anyhow!(
+            "No response: check network or no rmu at mac={},devid=0x{:02X}",
+            cmd.mac,
+            cmd.devid
+        ));
+    }
+
+    for reply in replies {
+        match reply {
+            Ok(resp) => {
+                let mac: MacAddress = resp.header().source_address().into();
+                println!(
+                    "Mac:{mac} Devid:0x{:02X}\n {:04X?}",
+                    resp.header().device_id(),
+                    resp.regops,
+                );
             }
+            Err(e) => eprintln!("Error: {e}"),
+        }
+    }
 
-            Ok(())
-        })
+    Ok(())
+}
+
+impl CommandOperation for RegOpCmd {
+    fn process(&self) -> anyhow::Result<()> {
+        let client = open_client(&self.interface, self.timeout_ms)?;
+        smol::block_on(proccmd(self, &client))
     }
 }
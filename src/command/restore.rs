@@ -0,0 +1,163 @@
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Context};
+use clap::Args;
+use mac_address::MacAddress;
+
+use mrmu::message::register::{RegOpRequest, RegOpRequestList, RegOpResponse};
+use mrmu::message::MessageOperation;
+use mrmu::reginfo::register_db;
+use mrmu::snapshot::{self, RegisterValue, RestoreWrite, Snapshot};
+use mrmu::transport::Transport;
+use mrmu::RmuClient;
+
+use super::{open_client, read_block, CommandOperation};
+
+/// Write a snapshot of `dump` back, only the registers differing on the device
+///
+/// Read only registers and fields are skipped, self clearing and write only
+/// bits written 0, indirect tables are not restored. Globals go first, port
+/// states last.
+#[derive(Args, Debug)]
+pub struct RestoreCmd {
+    #[arg(short, long)]
+    interface: String,
+
+    #[arg(short, long, default_value_t = 100)]
+    timeout_ms: u32,
+
+    #[arg(short, long)]
+    #[arg(default_value_t = String::from("01:50:43:00:00:03"))]
+    mac: String,
+
+    /// Device to restore, the one of the snapshot if omitted
+    #[arg(short, long, value_parser=clap_num::maybe_hex::<u8>)]
+    devid: Option<u8>,
+
+    /// Print the writes without doing them
+    #[arg(long, default_value_t = false)]
+    dry_run: bool,
+
+    /// Snapshot file written by dump
+    snapshot: PathBuf,
+}
+
+/// Values of the snapshot registers on the device, one frame per SMI device
+async fn read_live<T: Transport>(
+    client: &RmuClient<T>,
+    dmac: &[u8; 6],
+    devid: u8,
+    saved: &Snapshot,
+) -> anyhow::Result<Snapshot> {
+    let mut addrs: Vec<u8> = Vec::new();
+    for reg in &saved.registers {
+        if !addrs.contains(&reg.addr) {
+            addrs.push(reg.addr);
+        }
+    }
+
+    let mut live = Snapshot {
+        devid,
+        product_number: saved.product_number,
+        registers: Vec::new(),
+    };
+    for addr in addrs {
+        let regs: Vec<&RegisterValue> = saved.registers.iter().filter(|r| r.addr == addr).collect();
+        let offsets: Vec<u8> = regs.iter().map(|r| r.reg).collect();
+        let values = read_block(client, dmac, devid, addr, &offsets).await?;
+        for (reg, value) in regs.into_iter().zip(values) {
+            live.registers.push(RegisterValue {
+                value,
+                ..reg.clone()
+            });
+        }
+    }
+    Ok(live)
+}
+
+/// Do `writes` of one SMI device in a frame, reading each back
+async fn apply<T: Transport>(
+    client: &RmuClient<T>,
+    dmac: &[u8; 6],
+    devid: u8,
+    writes: &[RestoreWrite],
+) -> anyhow::Result<()> {
+    let mut regops = RegOpRequestList::new();
+    for write in writes {
+        regops.add_regop(RegOpRequest::Write {
+            addr: write.addr,
+            reg: write.reg,
+            data: write.value,
+        });
+        regops.add_regop(RegOpRequest::Read {
+            addr: write.addr,
+            reg: write.reg,
+        });
+    }
+
+    let resp = client.register_ops_one(dmac, devid, regops).await?;
+    let readbacks = resp.regops.as_ref().iter().filter_map(|op| match *op {
+        RegOpResponse::Read { data, .. } => Some(data),
+        _ => None,
+    });
+    let mut verified = 0;
+    for (write, readback) in writes.iter().zip(readbacks) {
+        if (readback ^ write.value) & write.verify_mask != 0 {
+            return Err(anyhow!(
+                "{} reads back 0x{:04X} after writing 0x{:04X}",
+                write.path,
+                readback,
+                write.value
+            ));
+        }
+        verified += 1;
+    }
+    if verified != writes.len() {
+        return Err(anyhow!("{} not verified", writes[verified].path));
+    }
+    Ok(())
+}
+
+async fn proccmd<T: Transport>(cmd: &RestoreCmd, client: &RmuClient<T>) -> anyhow::Result<()> {
+    let dmac = cmd.mac.parse::<MacAddress>()?.bytes();
+    let saved = Snapshot::load(&cmd.snapshot)
+        .with_context(|| format!("read {}", cmd.snapshot.display()))?;
+    let devid = cmd.devid.unwrap_or(saved.devid);
+
+    let prodno = client
+        .get_id_one(&dmac, devid)
+        .await?
+        .header()
+        .product_number();
+    if prodno != saved.product_number {
+        return Err(anyhow!(
+            "snapshot of product 0x{:04X}, devid 0x{:02X} is 0x{:04X}",
+            saved.product_number,
+            devid,
+            prodno
+        ));
+    }
+
+    let live = read_live(client, &dmac, devid, &saved).await?;
+    let writes = snapshot::restore_writes(&saved, &live, register_db());
+    for write in &writes {
+        println!("{}", write);
+    }
+    if cmd.dry_run {
+        println!("{} registers to write, dry run", writes.len());
+        return Ok(());
+    }
+
+    for block in writes.chunk_by(|a, b| a.addr == b.addr) {
+        apply(client, &dmac, devid, block).await?;
+    }
+    println!("{} registers written", writes.len());
+    Ok(())
+}
+
+impl CommandOperation for RestoreCmd {
+    fn process(&self) -> anyhow::Result<()> {
+        let client = open_client(&self.interface, self.timeout_ms)?;
+        smol::block_on(proccmd(self, &client))
+    }
+}
@@ -1,18 +1,13 @@
-use std::io::{Read, Write};
-use std::{io::ErrorKind, time::Duration};
-
 use clap::Args;
-use mac_address::mac_address_by_name;
-use smol::{future::FutureExt, Timer};
 
-use super::CommandOperation;
 use super::RMU_MULTICAST_ADDR;
-use crate::message::getid::GetIdRequest;
-use crate::message::getid::GetIdResponse;
-use crate::message::header::RequestHeader;
-use crate::message::MessageOperation;
-use crate::message_builder::MessageBuilder;
-use crate::packet_sock;
+use super::{open_client, CommandOperation};
+use mrmu::chip::{Chip, PortKind, DEFAULT_PORT_COUNT};
+use mrmu::message::register::{RegOpRequest, RegOpRequestList, RegOpResponse};
+use mrmu::message::MessageOperation;
+use mrmu::reginfo::{u16_get_bits, PortRegister, PortStatus};
+use mrmu::transport::Transport;
+use mrmu::RmuClient;
 
 /// Scan all marvell switch devices through multicast
 #[derive(Args, Debug)]
@@ -20,7 +15,7 @@ pub struct ScanCmd {
     #[arg(short, long)]
     interface: String,
 
-    #[arg(short, long, default_value_t = 1000)]
+    #[arg(short, long, default_value_t = 100)]
     timeout_ms: u32,
 }
 
@@ -45,80 +40,97 @@ impl std::fmt::Display for DevInfo {
             self.devid,
             self.prodno,
         );
+        if let Some(chip) = Chip::from_product_number(self.prodno) {
+            let _ = write!(f, " {}", chip);
+        }
 
         Ok(())
     }
 }
 
-impl CommandOperation for ScanCmd {
-    fn process(&self) -> anyhow::Result<()> {
-        smol::block_on(async {
-            let sock = packet_sock::create_rmu_sock(&self.interface)?;
-            let smac = mac_address_by_name(&self.interface)?.unwrap().bytes();
-
-            let mut seqno = 0;
-            let mut devs: Vec<DevInfo> = Vec::new();
-            let mut updown = true;
-
-            for devid in 0x00..=0x1F {
-                if updown {
-                    eprint!("\rStarting Scan |");
-                } else {
-                    eprint!("\rStarting Scan -");
-                }
-                updown = !updown;
-
-                let mut reqmsg = Into::<MessageBuilder<GetIdRequest>>::into(
-                    MessageBuilder::<RequestHeader>::new()
-                        .destination_address(&RMU_MULTICAST_ADDR)
-                        .source_address(&smac)
-                        .device_id(devid)
-                        .sequence_number(seqno),
-                )
-                .build()?;
-
-                let mut wbuf = [0u8; 60];
-                let _ = reqmsg.marshal(&mut wbuf)?;
-                sock.write_with(|mut s| s.write(&wbuf[..])).await?;
+async fn proccmd<T: Transport>(client: &RmuClient<T>) -> anyhow::Result<()> {
+    let mut devs: Vec<DevInfo> = Vec::new();
+    let mut updown = true;
+
+    for devid in 0x00..=0x1F {
+        if updown {
+            eprint!("\rStarting Scan |");
+        } else {
+            eprint!("\rStarting Scan -");
+        }
+        updown = !updown;
+
+        // multi device may response
+        let replies = match client.get_id(&RMU_MULTICAST_ADDR, devid).await {
+            Ok(replies) => replies,
+            Err(e) => {
+                eprintln!("Error: {e}");
+                continue;
+            }
+        };
+
+        for reply in replies {
+            match reply {
+                Ok(respmsg) => devs.push(DevInfo {
+                    mac: respmsg.header().source_address(),
+                    devid: respmsg.header().device_id(),
+                    prodno: respmsg.header().product_number(),
+                }),
+                Err(e) => eprintln!("Error: {e}"),
+            }
+        }
+    }
 
-                // loop if multi device response
-                loop {
-                    let mut rbuf = [0; 1514];
-                    let res = sock
-                        .read_with(|mut s| s.read(&mut rbuf))
-                        .or(async {
-                            Timer::after(Duration::from_millis(100)).await;
-                            Err(ErrorKind::TimedOut.into())
-                        })
-                        .await;
+    println!("\nScan done: {} devices", devs.len());
+    for dev in &devs {
+        println!(" {}", dev);
+        match port_kinds(client, dev).await {
+            Ok(kinds) => println!("  ports: {}", kinds.join(" ")),
+            Err(e) => eprintln!("Error: ports of {}: {e}", dev),
+        }
+    }
 
-                    match res {
-                        Ok(sz) if sz > 0 => {
-                            let respmsg = GetIdResponse::unmarshal(&rbuf[..sz])?;
-                            devs.push(DevInfo {
-                                mac: respmsg.header().source_address(),
-                                devid: respmsg.header().device_id(),
-                                prodno: respmsg.header().product_number(),
-                            });
-                        }
-                        Ok(_) => {}
-                        Err(e) => {
-                            if e.kind() != ErrorKind::TimedOut {
-                                eprintln!("Error: {e}");
-                            }
+    Ok(())
+}
 
-                            break;
-                        }
-                    }
-                }
+/// Interface of every port of `dev`, by the c_mode of its port_status
+async fn port_kinds<T: Transport>(
+    client: &RmuClient<T>,
+    dev: &DevInfo,
+) -> anyhow::Result<Vec<String>> {
+    let port_count = Chip::from_product_number(dev.prodno)
+        .map(|chip| chip.port_count())
+        .unwrap_or(DEFAULT_PORT_COUNT);
+    let mut regops = RegOpRequestList::new();
+    for port in 0..port_count {
+        regops.add_regop(RegOpRequest::Read {
+            addr: port,
+            reg: PortRegister::PortStatus as u8,
+        });
+    }
 
-                seqno += 1;
+    let resp = client.register_ops_one(&dev.mac, dev.devid, regops).await?;
+    Ok(resp
+        .regops
+        .as_ref()
+        .iter()
+        .filter_map(|op| match *op {
+            RegOpResponse::Read { addr, data, .. } => Some((addr, data)),
+            _ => None,
+        })
+        .map(|(port, status)| {
+            let c_mode = u16_get_bits(status, PortStatus::CMode);
+            match PortKind::from_c_mode(c_mode) {
+                Some(kind) => format!("{port}:{kind}"),
+                None => format!("{port}:c_mode=0x{c_mode:X}"),
             }
-
-            println!("\nScan done: {} devices", devs.len());
-            devs.iter().for_each(|dev| println!(" {}", dev));
-
-            Ok(())
         })
+        .collect())
+}
+
+impl CommandOperation for ScanCmd {
+    fn process(&self) -> anyhow::Result<()> {
+        let client = open_client(&self.interface, self.timeout_ms)?;
+        smol::block_on(proccmd(&client))
     }
 }
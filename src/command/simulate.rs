@@ -0,0 +1,127 @@
+use std::io::ErrorKind;
+use std::time::{Duration, Instant};
+
+use clap::Args;
+use mac_address::MacAddress;
+
+use super::CommandOperation;
+use mrmu::packet_sock::PacketSock;
+use mrmu::simulator::{SimDevice, Simulator};
+use mrmu::transport::Transport;
+
+/// Answer RMU requests on an interface as simulated switches
+///
+/// Meant for one end of a veth pair, the other end is used by a normal mrmu
+/// command, e.g.
+///   ip link add veth0 type veth peer name veth1
+///   mrmu simulate -i veth0 --device devid=0 --device devid=3,prodno=0x1510
+///   mrmu scan -i veth1
+#[derive(Args, Debug)]
+pub struct SimulateCmd {
+    #[arg(short, long)]
+    interface: String,
+
+    /// Simulated device: devid=[],mac=[],prodno=[],unsupported=[],max_regops=[]
+    ///
+    /// mac defaults to 00:50:43:00:00:<devid>, prodno to 0x1520 (88Q5152).
+    /// Requests with an unsupported code, the key can repeat, get an
+    /// ErrorResponse, as do op lists longer than max_regops.
+    #[arg(short, long, num_args = 1.., value_parser = parse_device)]
+    #[arg(default_value = "devid=0")]
+    device: Vec<DeviceSpec>,
+}
+
+#[derive(Clone, Debug)]
+struct DeviceSpec {
+    devid: u8,
+    mac: [u8; 6],
+    prodno: u16,
+    unsupported: Vec<u16>,
+    max_regops: Option<usize>,
+}
+
+fn parse_device(spec: &str) -> Result<DeviceSpec, String> {
+    let mut devid: u8 = 0;
+    let mut mac: Option<[u8; 6]> = None;
+    let mut prodno: u16 = 0x1520;
+    let mut unsupported = Vec::new();
+    let mut max_regops = None;
+
+    for para in spec.split(',') {
+        let (key, val) = para
+            .split_once('=')
+            .ok_or_else(|| format!("wrong device keyval pair: {}", para))?;
+
+        match key.trim().to_lowercase().as_str() {
+            "devid" => devid = clap_num::maybe_hex(val.trim())?,
+            "mac" => {
+                let addr = val
+                    .trim()
+                    .parse::<MacAddress>()
+                    .map_err(|e| e.to_string())?;
+                mac = Some(addr.bytes());
+            }
+            "prodno" => prodno = clap_num::maybe_hex(val.trim())?,
+            "unsupported" => unsupported.push(clap_num::maybe_hex(val.trim())?),
+            "max_regops" => max_regops = Some(clap_num::maybe_hex(val.trim())?),
+            _ => return Err(format!("unknown device key: {}", key)),
+        }
+    }
+
+    if devid > 0x1F {
+        return Err(format!("devid 0x{:02X} out of range", devid));
+    }
+
+    Ok(DeviceSpec {
+        devid,
+        mac: mac.unwrap_or([0x00, 0x50, 0x43, 0x00, 0x00, devid]),
+        prodno,
+        unsupported,
+        max_regops,
+    })
+}
+
+async fn proccmd<T: Transport>(cmd: &SimulateCmd, transport: &T) -> anyhow::Result<()> {
+    let mut sim = Simulator::new();
+    for spec in &cmd.device {
+        let mac: MacAddress = spec.mac.into();
+        println!(
+            "simulate mac:{mac} devid:0x{:02X} prodno:0x{:04X}",
+            spec.devid, spec.prodno
+        );
+        let mut dev = SimDevice::new(spec.devid, spec.mac, spec.prodno);
+        for code in &spec.unsupported {
+            dev.set_unsupported(*code);
+        }
+        if let Some(max) = spec.max_regops {
+            dev.set_max_regops(max);
+        }
+        sim.add_device(dev);
+    }
+
+    loop {
+        let mut rbuf = [0; 1514];
+        let deadline = Instant::now() + Duration::from_secs(1);
+        let sz = match transport.recv(&mut rbuf, deadline).await {
+            Ok(sz) => sz,
+            Err(e) if e.kind() == ErrorKind::TimedOut => continue,
+            Err(e) => return Err(e.into()),
+        };
+
+        match sim.handle_frame(&rbuf[..sz]) {
+            Ok(responses) => {
+                for resp in responses {
+                    transport.send(&resp).await?;
+                }
+            }
+            Err(e) => eprintln!("Error: drop frame: {e}"),
+        }
+    }
+}
+
+impl CommandOperation for SimulateCmd {
+    fn process(&self) -> anyhow::Result<()> {
+        let transport = PacketSock::open(&self.interface)?;
+        smol::block_on(proccmd(self, &transport))
+    }
+}
@@ -0,0 +1,96 @@
+use anyhow::anyhow;
+use clap::Args;
+use mac_address::MacAddress;
+
+use mrmu::message::register::{RegOpRequest, RegOpRequestList, RegOpResponse};
+use mrmu::reginfo::{register_db, Access, Device};
+use mrmu::transport::Transport;
+use mrmu::RmuClient;
+
+use super::{
+    check_register, detect_chip, open_client, print_register, read_block, set_fields,
+    CommandOperation,
+};
+
+/// Change fields of a port register, keeping the other bits
+///
+/// Reads the register, writes it back with the fields replaced and reads it
+/// again to verify the read/write fields.
+#[derive(Args, Debug)]
+pub struct WritePortRegCmd {
+    #[arg(short, long)]
+    interface: String,
+
+    #[arg(short, long, default_value_t = 100)]
+    timeout_ms: u32,
+
+    #[arg(short, long)]
+    #[arg(default_value_t = String::from("01:50:43:00:00:03"))]
+    mac: String,
+
+    #[arg(short, long, value_parser=clap_num::maybe_hex::<u8>)]
+    devid: u8,
+
+    #[arg(short, long)]
+    portid: u8,
+
+    /// Register name of the description, e.g. port_control0
+    #[arg(short, long)]
+    register: String,
+
+    /// Comma separated field=value assignments, e.g. port_state=3,egress_floods=all
+    #[arg(short, long, required = true, num_args = 1, value_delimiter = ',')]
+    set: Vec<String>,
+}
+
+async fn proccmd<T: Transport>(cmd: &WritePortRegCmd, client: &RmuClient<T>) -> anyhow::Result<()> {
+    let dmac = cmd.mac.parse::<MacAddress>()?.bytes();
+    let desc = register_db()
+        .find(Device::Port, &cmd.register)
+        .map_err(anyhow::Error::msg)?;
+    let chip = detect_chip(client, &dmac, cmd.devid).await?;
+    check_register(chip, desc, Some(cmd.portid))?;
+    let name = &desc.name;
+    let layout = &desc.fields;
+    let reg = desc.offset;
+
+    let old = read_block(client, &dmac, cmd.devid, cmd.portid, &[reg]).await?[0];
+    let new = set_fields(name, old, layout, &cmd.set)?;
+
+    let mut regops = RegOpRequestList::new();
+    regops.add_regop(RegOpRequest::Write {
+        addr: cmd.portid,
+        reg,
+        data: new,
+    });
+    regops.add_regop(RegOpRequest::Read {
+        addr: cmd.portid,
+        reg,
+    });
+
+    let resp = client.register_ops_one(&dmac, cmd.devid, regops).await?;
+    let readback = match resp.regops.as_ref().get(1) {
+        Some(&RegOpResponse::Read { data, .. }) => data,
+        _ => return Err(anyhow!("verify {} fail", name)),
+    };
+
+    println!("{}(H): {:04X} -> {:04X}", name, old, readback);
+    // self clearing, write only and status bits need not read back as written
+    let verify_mask = desc.mask_of(Access::Rw);
+    if readback & verify_mask != new & verify_mask {
+        return Err(anyhow!(
+            "{} reads back 0x{:04X} after writing 0x{:04X}",
+            name,
+            readback,
+            new
+        ));
+    }
+    print_register(name, readback, layout, &None)
+}
+
+impl CommandOperation for WritePortRegCmd {
+    fn process(&self) -> anyhow::Result<()> {
+        let client = open_client(&self.interface, self.timeout_ms)?;
+        smol::block_on(proccmd(self, &client))
+    }
+}
@@ -0,0 +1,266 @@
+//! Human readable rendering of RMU frames, e.g. out of a capture, and pairing
+//! of requests with their responses
+use std::fmt::Write;
+use std::time::Duration;
+
+use mac_address::MacAddress;
+
+use crate::client::is_reply_to;
+use crate::error::RmuError;
+use crate::message::customer_info_read::CustomerInfoReadResponse;
+use crate::message::fw_version::FwVersionResponse;
+use crate::message::header::{RequestHeader, ResponseHeader};
+use crate::message::register::{RegOpRequest, RegOpResponse, RegisterRequest, RegisterResponse};
+use crate::message::response_error::{DeviceError, ResponseError, ResponseErrorExt};
+use crate::message::version_read::VersionReadResponse;
+use crate::message::{check_len, MessageHeaderOperation, MessageOperation};
+use crate::message_code::MessageCode;
+use crate::packet_sock::ETH_P_RMU;
+
+/// Register op in the `--actions` syntax of `regop`
+pub fn format_regop_request(op: &RegOpRequest) -> String {
+    match *op {
+        RegOpRequest::Read { addr, reg } => format!("READ:addr=0x{:02X},reg=0x{:02X}", addr, reg),
+        RegOpRequest::Write { addr, reg, data } => format!(
+            "WRITE:addr=0x{:02X},reg=0x{:02X},data=0x{:04X}",
+            addr, reg, data
+        ),
+        RegOpRequest::WaitOnBit0 { addr, reg, bit } => format!(
+            "WaitOnBit0:addr=0x{:02X},reg=0x{:02X},bit={}",
+            addr, reg, bit
+        ),
+        RegOpRequest::WaitOnBit1 { addr, reg, bit } => format!(
+            "WaitOnBit1:addr=0x{:02X},reg=0x{:02X},bit={}",
+            addr, reg, bit
+        ),
+        RegOpRequest::EndOfList => "EndOfList".to_string(),
+    }
+}
+
+/// Register op result, the request part as in `format_regop_request`
+pub fn format_regop_response(op: &RegOpResponse) -> String {
+    match *op {
+        RegOpResponse::Read { addr, reg, data } => format!(
+            "READ:addr=0x{:02X},reg=0x{:02X} -> data=0x{:04X}",
+            addr, reg, data
+        ),
+        RegOpResponse::Write { addr, reg, data } => format!(
+            "WRITE:addr=0x{:02X},reg=0x{:02X},data=0x{:04X}",
+            addr, reg, data
+        ),
+        RegOpResponse::WaitOnBit0 {
+            addr,
+            reg,
+            bit,
+            result,
+        } => format!(
+            "WaitOnBit0:addr=0x{:02X},reg=0x{:02X},bit={} -> result=0x{:02X}",
+            addr, reg, bit, result
+        ),
+        RegOpResponse::WaitOnBit1 {
+            addr,
+            reg,
+            bit,
+            result,
+        } => format!(
+            "WaitOnBit1:addr=0x{:02X},reg=0x{:02X},bit={} -> result=0x{:02X}",
+            addr, reg, bit, result
+        ),
+        RegOpResponse::EndOfList => "EndOfList".to_string(),
+    }
+}
+
+fn format_code(code: u16) -> String {
+    match MessageCode::try_from(code) {
+        Ok(known) => format!("code:0x{:04X}({:?})", code, known),
+        Err(_) => format!("code:0x{:04X}", code),
+    }
+}
+
+/// Whether `frame` carries the RMU ethertype
+pub fn is_rmu_frame(frame: &[u8]) -> bool {
+    frame.get(12..14) == Some(&(ETH_P_RMU as u16).to_be_bytes())
+}
+
+/// Decode a RMU frame into a summary line and one indented line per detail
+///
+/// Frames of other ethertypes give `None`. The tag tells a request (from_cpu)
+/// from a response (to_cpu), the code picks the payload type.
+pub fn decode_frame(frame: &[u8]) -> Result<Option<String>, RmuError> {
+    if !is_rmu_frame(frame) {
+        return Ok(None);
+    }
+
+    // both headers are 28 bytes
+    check_len(frame, 28)?;
+    let mut out = String::new();
+    match frame[16] & 0xC0 {
+        0x40 => decode_request(frame, &mut out)?,
+        0x00 => decode_response(frame, &mut out)?,
+        _ => {
+            let _ = write!(out, "unknown  dsa tag 0x{:02X}", frame[16]);
+        }
+    }
+
+    Ok(Some(out))
+}
+
+fn decode_request(frame: &[u8], out: &mut String) -> Result<(), RmuError> {
+    let hdr = RequestHeader::unmarshal(frame)?;
+    let _ = write!(
+        out,
+        "request  {} > {} devid:0x{:02X} seq:0x{:02X} {}",
+        MacAddress::from(hdr.source_address()),
+        MacAddress::from(hdr.destination_address()),
+        hdr.device_id(),
+        hdr.sequence_number(),
+        format_code(hdr.code()),
+    );
+
+    if let Ok(MessageCode::RwRegister) = hdr.message_code() {
+        let req = RegisterRequest::unmarshal(frame)?;
+        for op in req.regops.as_ref() {
+            let _ = write!(out, "\n  {}", format_regop_request(op));
+        }
+    }
+
+    Ok(())
+}
+
+fn decode_response(frame: &[u8], out: &mut String) -> Result<(), RmuError> {
+    let hdr = ResponseHeader::unmarshal(frame)?;
+    let _ = write!(
+        out,
+        "response {} > {} devid:0x{:02X} seq:0x{:02X} prodno:0x{:04X} {}",
+        MacAddress::from(hdr.source_address()),
+        MacAddress::from(hdr.destination_address()),
+        hdr.device_id(),
+        hdr.sequence_number(),
+        hdr.product_number(),
+        format_code(hdr.code()),
+    );
+
+    let Ok(code) = hdr.message_code() else {
+        return Ok(());
+    };
+    match code {
+        MessageCode::GetId => (),
+        MessageCode::VersionRead => {
+            let resp = VersionReadResponse::unmarshal(frame)?;
+            let _ = write!(out, "\n  crc32:0x{:08X}", resp.crc32);
+        }
+        MessageCode::CustomerInfoRead => {
+            let resp = CustomerInfoReadResponse::unmarshal(frame)?;
+            let _ = write!(out, "\n  info: {}", resp.info.to_string_lossy());
+        }
+        MessageCode::FwVersionGet => {
+            let resp = FwVersionResponse::unmarshal(frame)?;
+            let _ = write!(
+                out,
+                "\n  api:{} variant:{} release:{}\n  build: {}",
+                resp.api_number(),
+                resp.variant_number(),
+                resp.release_number(),
+                resp.build_string()
+            );
+        }
+        MessageCode::RwRegister => {
+            let resp = RegisterResponse::unmarshal(frame)?;
+            for op in resp.regops.as_ref() {
+                let _ = write!(out, "\n  {}", format_regop_response(op));
+            }
+        }
+        MessageCode::ErrorResponse => {
+            let err = DeviceError::from(ResponseError::unmarshal(frame)?);
+            let _ = write!(out, "\n  {}", err);
+        }
+        MessageCode::ErrorResponseEx => {
+            let err = DeviceError::from(ResponseErrorExt::unmarshal(frame)?);
+            let _ = write!(out, "\n  {}", err);
+        }
+    }
+
+    Ok(())
+}
+
+/// Request a response was matched to
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Correlation {
+    /// Id the request was observed with
+    pub request: usize,
+    pub latency: Duration,
+}
+
+struct Pending {
+    id: usize,
+    header: RequestHeader,
+    at: Duration,
+    answered: bool,
+}
+
+/// Pairs responses seen on the wire with the requests before them
+///
+/// Requests stay pending for `window`, a multicast one can collect replies of
+/// several devices meanwhile. A request reusing the sequence number of a
+/// pending one from the same requester to the same devid replaces it.
+pub struct Correlator {
+    window: Duration,
+    pending: Vec<Pending>,
+}
+
+impl Correlator {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Feed frame `id` seen at `at`, get the request it answers if it is a response
+    pub fn observe(&mut self, id: usize, frame: &[u8], at: Duration) -> Option<Correlation> {
+        if !is_rmu_frame(frame) || frame.len() < 28 {
+            return None;
+        }
+
+        if frame[16] & 0xC0 == 0x40 {
+            let header = RequestHeader::unmarshal(frame).ok()?;
+            self.pending.retain(|p| {
+                !(p.header.source_address() == header.source_address()
+                    && p.header.device_id() == header.device_id()
+                    && p.header.sequence_number() == header.sequence_number())
+            });
+            self.pending.push(Pending {
+                id,
+                header,
+                at,
+                answered: false,
+            });
+            return None;
+        }
+
+        let requester = ResponseHeader::unmarshal(frame).ok()?.destination_address();
+        let pending =
+            self.pending.iter_mut().rev().find(|p| {
+                p.header.source_address() == requester && is_reply_to(&p.header, frame)
+            })?;
+        pending.answered = true;
+
+        Some(Correlation {
+            request: pending.id,
+            latency: at.saturating_sub(pending.at),
+        })
+    }
+
+    /// Drop the requests older than the window at `now`, return the unanswered ones
+    pub fn expire(&mut self, now: Duration) -> Vec<usize> {
+        let mut unanswered = Vec::new();
+        self.pending.retain(|p| {
+            let alive = now.saturating_sub(p.at) < self.window;
+            if !alive && !p.answered {
+                unanswered.push(p.id);
+            }
+            alive
+        });
+        unanswered
+    }
+}
@@ -0,0 +1,38 @@
+use std::io;
+
+use thiserror::Error;
+
+use crate::message::response_error::DeviceError;
+
+/// Failures of building, parsing or exchanging RMU messages
+#[derive(Debug, Error)]
+pub enum RmuError {
+    /// Frame or buffer shorter than the message needs
+    #[error("truncated: need {need} bytes, got {got}")]
+    Truncated { need: usize, got: usize },
+
+    #[error("unknown message code 0x{0:04X}")]
+    UnknownCode(u16),
+
+    /// Valid code, but not the message that was asked for
+    #[error("message code 0x{got:04X} mismatch, expect 0x{expect:04X}")]
+    CodeMismatch { expect: u16, got: u16 },
+
+    /// Register op with an optype or opcode not in the spec
+    #[error("unknown register optype/opcode 0x{0:X}")]
+    BadOpType(u8),
+
+    /// Register op list longer than one frame holds
+    #[error("{count} register ops, a frame holds {max}")]
+    TooManyRegOps { count: usize, max: usize },
+
+    /// The device answered with ErrorResponse/ErrorResponseEx
+    #[error(transparent)]
+    DeviceError(#[from] DeviceError),
+
+    #[error("timeout waiting for response")]
+    Timeout,
+
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
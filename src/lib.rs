@@ -0,0 +1,29 @@
+//! Marvell RMU (remote management unit) access for 88Q5151/88Q5152/88Q5192
+//!
+//! `message` and `message_builder` hold the wire format, `transport` the link
+//! the frames go over and `client` the request/response exchange on top.
+//! `simulator` answers requests in-process, e.g. for tests without a switch.
+//! `capture` records the traffic of a session as pcapng, `decode` renders
+//! captured frames readable. `reginfo` names registers and their fields, from
+//! a TOML description built in or given at run time. `chip` tells the models
+//! apart by product number. `snapshot` keeps the registers of a switch for
+//! later comparison.
+#![cfg_attr(debug_assertions, allow(dead_code, unused_imports))]
+
+pub mod capture;
+pub mod chip;
+pub mod client;
+pub mod decode;
+pub mod error;
+pub mod message;
+pub mod message_builder;
+pub mod message_code;
+pub mod packet_sock;
+pub mod reginfo;
+pub mod simulator;
+pub mod snapshot;
+pub mod transport;
+
+pub use client::{Reply, RmuClient};
+pub use error::RmuError;
+pub use transport::Transport;
@@ -1,25 +1,46 @@
-#![allow(dead_code)]
 #![cfg_attr(debug_assertions, allow(dead_code, unused_imports))]
 
 mod command;
-mod message;
-mod message_builder;
-mod message_code;
-mod packet_sock;
-mod reginfo;
+
+use std::path::PathBuf;
 
 use clap::Parser;
 
 use command::{CommandOperation, Commands};
+use mrmu::message::register::MAX_REGOPS_PER_FRAME;
 
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
 struct Cli {
     #[command(subcommand)]
     cmd: Commands,
+
+    /// Write every sent and received RMU frame to a pcapng file
+    #[arg(long, global = true, value_name = "FILE")]
+    capture: Option<PathBuf>,
+
+    /// Register description in TOML, laid over the builtin one
+    #[arg(long, global = true, value_name = "FILE")]
+    regdb: Option<PathBuf>,
+
+    /// Send a request again this many times when no reply came back
+    #[arg(long, global = true, value_name = "N", default_value_t = 0)]
+    retries: u32,
+
+    /// Most register ops per frame, longer lists go out in several frames
+    #[arg(long, global = true, value_name = "N", default_value_t = MAX_REGOPS_PER_FRAME as u16)]
+    #[arg(value_parser = clap::value_parser!(u16).range(1..=MAX_REGOPS_PER_FRAME as i64))]
+    max_regops: u16,
 }
 
 fn main() -> anyhow::Result<()> {
     let cli = Cli::parse();
+    if let Some(path) = &cli.capture {
+        mrmu::packet_sock::capture_to(path)?;
+    }
+    if let Some(path) = &cli.regdb {
+        mrmu::reginfo::load_register_db(path)?;
+    }
+    command::set_client_limits(cli.retries, cli.max_regops.into());
     cli.cmd.process()
 }
@@ -3,11 +3,14 @@ pub mod fw_version;
 pub mod getid;
 pub mod header;
 pub mod register;
+pub mod response_error;
 pub mod version_read;
 
-use std::usize;
-
+use crate::error::RmuError;
 use crate::message_code::MessageCode;
+use response_error::{DeviceError, ResponseError, ResponseErrorExt};
+
+pub const RMU_MULTICAST_ADDR: [u8; 6] = [0x01, 0x50, 0x43, 0x00, 0x00, 0x03];
 
 pub struct Message<P> {
     pub payload: P,
@@ -17,10 +20,10 @@ pub trait MessageHeaderOperation {
     type Output: MessageHeaderOperation;
 
     fn wire_size(&self) -> usize;
-    fn marshal(&mut self, buffer: &mut [u8]) -> anyhow::Result<usize>;
-    fn unmarshal(buffer: &[u8]) -> anyhow::Result<Self::Output>;
+    fn marshal(&mut self, buffer: &mut [u8]) -> Result<usize, RmuError>;
+    fn unmarshal(buffer: &[u8]) -> Result<Self::Output, RmuError>;
 
-    fn message_code(&self) -> MessageCode;
+    fn message_code(&self) -> Result<MessageCode, RmuError>;
 }
 
 pub trait MessageOperation {
@@ -30,33 +33,65 @@ pub trait MessageOperation {
     fn message_code() -> MessageCode;
 
     fn wire_size(&self) -> usize;
-    fn marshal(&mut self, buffer: &mut [u8]) -> anyhow::Result<usize>;
-    fn unmarshal(buffer: &[u8]) -> anyhow::Result<Self::Output>;
+    fn marshal(&mut self, buffer: &mut [u8]) -> Result<usize, RmuError>;
+    fn unmarshal(buffer: &[u8]) -> Result<Self::Output, RmuError>;
 
     fn header(&self) -> &Self::Header;
     fn header_mut(&mut self) -> &mut Self::Header;
 }
 
-pub fn marshal<T: MessageOperation>(message: &mut T, buf: &mut [u8]) -> anyhow::Result<usize> {
+pub fn marshal<T: MessageOperation>(message: &mut T, buf: &mut [u8]) -> Result<usize, RmuError> {
     message.marshal(buf)
 }
 
-pub fn unmarshal<T: MessageOperation<Output = T>>(buffer: &[u8]) -> anyhow::Result<T> {
+pub fn unmarshal<T: MessageOperation<Output = T>>(buffer: &[u8]) -> Result<T, RmuError> {
     let header = T::Header::unmarshal(buffer)?;
-    let code = header.message_code();
+    let code = header.message_code()?;
     if code != T::message_code() {
         match code {
             MessageCode::ErrorResponse => {
-                todo!()
+                let resp = ResponseError::unmarshal(buffer)?;
+                return Err(DeviceError::from(resp).into());
             }
             MessageCode::ErrorResponseEx => {
-                todo!()
+                let resp = ResponseErrorExt::unmarshal(buffer)?;
+                return Err(DeviceError::from(resp).into());
+            }
+            _ => {
+                return Err(RmuError::CodeMismatch {
+                    expect: T::message_code() as u16,
+                    got: code as u16,
+                })
             }
-            _ => return Err(anyhow::anyhow!("code:{} mismatch", code as u16)),
         }
     }
 
-    Ok(T::unmarshal(buffer)?)
+    T::unmarshal(buffer)
+}
+
+/// Fail with `RmuError::Truncated` unless `buffer` holds `need` bytes
+pub(crate) fn check_len(buffer: &[u8], need: usize) -> Result<(), RmuError> {
+    if buffer.len() < need {
+        return Err(RmuError::Truncated {
+            need,
+            got: buffer.len(),
+        });
+    }
+    Ok(())
+}
+
+/// `N` bytes at `offset`, or `RmuError::Truncated`
+pub(crate) fn read_bytes<const N: usize>(
+    buffer: &[u8],
+    offset: usize,
+) -> Result<[u8; N], RmuError> {
+    buffer
+        .get(offset..offset + N)
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or(RmuError::Truncated {
+            need: offset + N,
+            got: buffer.len(),
+        })
 }
 
 pub fn prealloc_buffer(msg: &impl MessageOperation) -> Vec<u8> {
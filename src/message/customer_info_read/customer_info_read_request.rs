@@ -1,3 +1,4 @@
+use crate::error::RmuError;
 use crate::message::MessageHeaderOperation;
 use crate::message_builder::MessageBuilder;
 use crate::message_builder::MessageBuilderOperation;
@@ -33,14 +34,14 @@ impl MessageOperation for CustomerInfoReadRequest {
     }
 
     fn wire_size(&self) -> usize {
-        self.header.wire_size() + 0
+        self.header.wire_size()
     }
 
-    fn marshal(&mut self, buffer: &mut [u8]) -> anyhow::Result<usize> {
+    fn marshal(&mut self, buffer: &mut [u8]) -> Result<usize, RmuError> {
         self.header.marshal(buffer)
     }
 
-    fn unmarshal(buffer: &[u8]) -> anyhow::Result<Self::Output> {
+    fn unmarshal(buffer: &[u8]) -> Result<Self::Output, RmuError> {
         let header = RequestHeader::unmarshal(buffer)?;
         Ok(Self { header })
     }
@@ -55,17 +56,15 @@ impl MessageOperation for CustomerInfoReadRequest {
 }
 
 impl TryFrom<RequestHeader> for CustomerInfoReadRequest {
-    type Error = anyhow::Error;
+    type Error = RmuError;
 
     fn try_from(value: RequestHeader) -> Result<Self, Self::Error> {
         let code: MessageCode = value.code.try_into()?;
         if code != CODE {
-            return Err(anyhow::anyhow!(
-                "{}:{} code mismatch {}",
-                file!(),
-                line!(),
-                value.code
-            ));
+            return Err(RmuError::CodeMismatch {
+                expect: CODE as u16,
+                got: value.code,
+            });
         }
 
         Ok(Self { header: value })
@@ -73,7 +72,7 @@ impl TryFrom<RequestHeader> for CustomerInfoReadRequest {
 }
 
 impl MessageBuilderOperation for CustomerInfoReadRequest {
-    fn finalize(self) -> anyhow::Result<Self> {
+    fn finalize(self) -> Result<Self, RmuError> {
         Ok(self)
     }
 }
@@ -1,9 +1,7 @@
-use std::ffi::CStr;
 use std::ffi::CString;
 
-use anyhow::Context;
-
-use crate::message::MessageHeaderOperation;
+use crate::error::RmuError;
+use crate::message::{check_len, read_bytes, MessageHeaderOperation};
 use crate::message::{header::ResponseHeader, MessageOperation};
 use crate::message_builder::MessageBuilder;
 use crate::message_builder::MessageBuilderOperation;
@@ -46,7 +44,8 @@ impl MessageOperation for CustomerInfoReadResponse {
         self.header.wire_size() + self.payload_wire_size()
     }
 
-    fn marshal(&mut self, buffer: &mut [u8]) -> anyhow::Result<usize> {
+    fn marshal(&mut self, buffer: &mut [u8]) -> Result<usize, RmuError> {
+        check_len(buffer, self.wire_size())?;
         let (hbuf, pbuf) = buffer.split_at_mut(self.header.wire_size());
         let _size = self.header.marshal(hbuf)?;
 
@@ -55,16 +54,18 @@ impl MessageOperation for CustomerInfoReadResponse {
             pbuf[..31].copy_from_slice(&sbytes[..31]);
             pbuf[31] = b'\0';
         } else {
-            pbuf.copy_from_slice(sbytes);
+            pbuf[..sbytes.len()].copy_from_slice(sbytes);
         }
 
         Ok(self.wire_size())
     }
 
-    fn unmarshal(buffer: &[u8]) -> anyhow::Result<Self::Output> {
-        let header = ResponseHeader::unmarshal(buffer).context("unmarshal fail")?;
+    fn unmarshal(buffer: &[u8]) -> Result<Self::Output, RmuError> {
+        let header = ResponseHeader::unmarshal(buffer)?;
         let (_, pbuf) = buffer.split_at(header.wire_size());
-        let info: CString = CStr::from_bytes_until_nul(pbuf).unwrap().into();
+        let raw: [u8; 32] = read_bytes(pbuf, 0)?;
+        let len = raw.iter().position(|&c| c == b'\0').unwrap_or(raw.len());
+        let info = CString::new(&raw[..len]).unwrap_or_default();
 
         Ok(Self { header, info })
     }
@@ -79,17 +80,15 @@ impl MessageOperation for CustomerInfoReadResponse {
 }
 
 impl TryFrom<ResponseHeader> for CustomerInfoReadResponse {
-    type Error = anyhow::Error;
+    type Error = RmuError;
 
     fn try_from(value: ResponseHeader) -> Result<Self, Self::Error> {
         let code: MessageCode = value.code.try_into()?;
         if code != CODE {
-            return Err(anyhow::anyhow!(
-                "{}:{} code mismatch {}",
-                file!(),
-                line!(),
-                value.code
-            ));
+            return Err(RmuError::CodeMismatch {
+                expect: CODE as u16,
+                got: value.code,
+            });
         }
 
         Ok(Self {
@@ -107,7 +106,7 @@ impl MessageBuilder<CustomerInfoReadResponse> {
 }
 
 impl MessageBuilderOperation for CustomerInfoReadResponse {
-    fn finalize(mut self) -> anyhow::Result<Self> {
+    fn finalize(mut self) -> Result<Self, RmuError> {
         let mut len = self.header.length_type();
         len += self.payload_wire_size() as u16;
         self.header.set_length_type(len);
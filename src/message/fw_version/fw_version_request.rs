@@ -1,3 +1,4 @@
+use crate::error::RmuError;
 use crate::message::header::RequestHeader;
 use crate::message::{MessageHeaderOperation, MessageOperation};
 use crate::message_builder::MessageBuilder;
@@ -34,11 +35,11 @@ impl MessageOperation for FwVersionRequest {
         self.header.wire_size()
     }
 
-    fn marshal(&mut self, buffer: &mut [u8]) -> anyhow::Result<usize> {
+    fn marshal(&mut self, buffer: &mut [u8]) -> Result<usize, RmuError> {
         self.header.marshal(buffer)
     }
 
-    fn unmarshal(buffer: &[u8]) -> anyhow::Result<Self::Output> {
+    fn unmarshal(buffer: &[u8]) -> Result<Self::Output, RmuError> {
         let header = RequestHeader::unmarshal(buffer)?;
         Ok(Self { header })
     }
@@ -53,17 +54,15 @@ impl MessageOperation for FwVersionRequest {
 }
 
 impl TryFrom<RequestHeader> for FwVersionRequest {
-    type Error = anyhow::Error;
+    type Error = RmuError;
 
     fn try_from(value: RequestHeader) -> Result<Self, Self::Error> {
         let code: MessageCode = value.code.try_into()?;
         if code != CODE {
-            return Err(anyhow::anyhow!(
-                "{}:{} message type mismatch {}",
-                file!(),
-                line!(),
-                value.code,
-            ));
+            return Err(RmuError::CodeMismatch {
+                expect: CODE as u16,
+                got: value.code,
+            });
         }
 
         Ok(Self { header: value })
@@ -80,7 +79,7 @@ impl From<MessageBuilder<RequestHeader>> for MessageBuilder<FwVersionRequest> {
 }
 
 impl MessageBuilderOperation for FwVersionRequest {
-    fn finalize(self) -> anyhow::Result<Self> {
+    fn finalize(self) -> Result<Self, RmuError> {
         Ok(self)
     }
 }
@@ -1,8 +1,8 @@
-use std::ffi::CStr;
-
+use crate::error::RmuError;
 use crate::message_builder::MessageBuilderOperation;
 use crate::{
-    message::{header::ResponseHeader, MessageHeaderOperation, MessageOperation},
+    message::{check_len, header::ResponseHeader, read_bytes},
+    message::{MessageHeaderOperation, MessageOperation},
     message_builder::MessageBuilder,
     message_code::MessageCode,
 };
@@ -31,13 +31,18 @@ impl FwVersionResponse {
     }
 
     pub fn build_string(&self) -> String {
-        let cstr = CStr::from_bytes_until_nul(&self.build_string).unwrap();
-        String::from_utf8_lossy(cstr.to_bytes()).to_string()
+        let len = self
+            .build_string
+            .iter()
+            .position(|&c| c == b'\0')
+            .unwrap_or(self.build_string.len());
+        String::from_utf8_lossy(&self.build_string[..len]).to_string()
     }
 
     pub fn payload_wire_size(&self) -> usize {
         let mut size = std::mem::size_of_val(&self.api_number);
         size += std::mem::size_of_val(&self.variant_number);
+        size += std::mem::size_of_val(&self.release_number);
         size += std::mem::size_of_val(&self.build_string);
 
         size
@@ -61,11 +66,10 @@ impl FwVersionResponse {
     pub fn set_build_string(&mut self, val: &str) -> &mut Self {
         let max_len = self.build_string.len();
         if val.len() >= max_len {
-            self.build_string
-                .copy_from_slice(&val.as_bytes()[..max_len - 1]);
+            self.build_string[..max_len - 1].copy_from_slice(&val.as_bytes()[..max_len - 1]);
             self.build_string[max_len - 1] = b'\0';
         } else {
-            self.build_string.copy_from_slice(&val.as_bytes());
+            self.build_string[..val.len()].copy_from_slice(val.as_bytes());
             self.build_string[val.len()] = b'\0';
         }
 
@@ -100,7 +104,8 @@ impl MessageOperation for FwVersionResponse {
         self.header.wire_size() + self.payload_wire_size()
     }
 
-    fn marshal(&mut self, buffer: &mut [u8]) -> anyhow::Result<usize> {
+    fn marshal(&mut self, buffer: &mut [u8]) -> Result<usize, RmuError> {
+        check_len(buffer, self.wire_size())?;
         let (hbuf, pbuf) = buffer.split_at_mut(self.header.wire_size());
         self.header.marshal(hbuf)?;
 
@@ -112,16 +117,16 @@ impl MessageOperation for FwVersionResponse {
         Ok(self.wire_size())
     }
 
-    fn unmarshal(buffer: &[u8]) -> anyhow::Result<Self::Output> {
+    fn unmarshal(buffer: &[u8]) -> Result<Self::Output, RmuError> {
         let header = ResponseHeader::unmarshal(buffer)?;
         let (_, pbuf) = buffer.split_at(header.wire_size());
 
         Ok(Self {
             header,
-            api_number: u16::from_be_bytes(pbuf[0..2].try_into().unwrap()),
-            variant_number: u16::from_be_bytes(pbuf[2..4].try_into().unwrap()),
-            release_number: u16::from_be_bytes(pbuf[4..6].try_into().unwrap()),
-            build_string: pbuf[6..262].try_into().unwrap(),
+            api_number: u16::from_be_bytes(read_bytes(pbuf, 0)?),
+            variant_number: u16::from_be_bytes(read_bytes(pbuf, 2)?),
+            release_number: u16::from_be_bytes(read_bytes(pbuf, 4)?),
+            build_string: read_bytes(pbuf, 6)?,
         })
     }
 
@@ -135,17 +140,15 @@ impl MessageOperation for FwVersionResponse {
 }
 
 impl TryFrom<ResponseHeader> for FwVersionResponse {
-    type Error = anyhow::Error;
+    type Error = RmuError;
 
     fn try_from(value: ResponseHeader) -> Result<Self, Self::Error> {
         let code: MessageCode = value.code.try_into()?;
         if code != CODE {
-            return Err(anyhow::anyhow!(
-                "{}:{} message type mismatch {}",
-                file!(),
-                line!(),
-                value.code,
-            ));
+            return Err(RmuError::CodeMismatch {
+                expect: CODE as u16,
+                got: value.code,
+            });
         }
 
         Ok(Self {
@@ -190,7 +193,7 @@ impl From<MessageBuilder<ResponseHeader>> for MessageBuilder<FwVersionResponse>
 }
 
 impl MessageBuilderOperation for FwVersionResponse {
-    fn finalize(mut self) -> anyhow::Result<Self> {
+    fn finalize(mut self) -> Result<Self, RmuError> {
         let len = self.header.length_type() + self.payload_wire_size() as u16;
         self.header.set_length_type(len);
         Ok(self)
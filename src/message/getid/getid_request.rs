@@ -1,3 +1,4 @@
+use crate::error::RmuError;
 use crate::message::header::RequestHeader;
 use crate::message::MessageHeaderOperation;
 use crate::message::MessageOperation;
@@ -30,14 +31,14 @@ impl MessageOperation for GetIdRequest {
     }
 
     fn wire_size(&self) -> usize {
-        self.header.wire_size() + 0
+        self.header.wire_size()
     }
 
-    fn marshal(&mut self, buffer: &mut [u8]) -> anyhow::Result<usize> {
+    fn marshal(&mut self, buffer: &mut [u8]) -> Result<usize, RmuError> {
         self.header.marshal(buffer)
     }
 
-    fn unmarshal(buffer: &[u8]) -> anyhow::Result<Self::Output> {
+    fn unmarshal(buffer: &[u8]) -> Result<Self::Output, RmuError> {
         let header = RequestHeader::unmarshal(buffer)?;
         Ok(GetIdRequest { header })
     }
@@ -52,17 +53,15 @@ impl MessageOperation for GetIdRequest {
 }
 
 impl TryFrom<RequestHeader> for GetIdRequest {
-    type Error = anyhow::Error;
+    type Error = RmuError;
 
     fn try_from(value: RequestHeader) -> Result<Self, Self::Error> {
         let code: MessageCode = value.code.try_into()?;
         if code != MessageCode::GetId {
-            return Err(anyhow::anyhow!(
-                "{}:{} code mismatch {}",
-                file!(),
-                line!(),
-                value.code
-            ));
+            return Err(RmuError::CodeMismatch {
+                expect: MessageCode::GetId as u16,
+                got: value.code,
+            });
         }
 
         Ok(Self { header: value })
@@ -79,7 +78,7 @@ impl From<MessageBuilder<RequestHeader>> for MessageBuilder<GetIdRequest> {
 }
 
 impl MessageBuilderOperation for GetIdRequest {
-    fn finalize(self) -> anyhow::Result<Self> {
+    fn finalize(self) -> Result<Self, RmuError> {
         Ok(self)
     }
 }
@@ -1,22 +1,15 @@
+use crate::error::RmuError;
 use crate::message::MessageHeaderOperation;
 use crate::message::{header::ResponseHeader, MessageOperation};
 use crate::message_builder::MessageBuilder;
 use crate::message_builder::MessageBuilderOperation;
 use crate::message_code::MessageCode;
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct GetIdResponse {
     header: ResponseHeader,
 }
 
-impl Default for GetIdResponse {
-    fn default() -> Self {
-        GetIdResponse {
-            header: Default::default(),
-        }
-    }
-}
-
 impl MessageOperation for GetIdResponse {
     type Output = Self;
     type Header = ResponseHeader;
@@ -26,14 +19,14 @@ impl MessageOperation for GetIdResponse {
     }
 
     fn wire_size(&self) -> usize {
-        self.header.wire_size() + 0
+        self.header.wire_size()
     }
 
-    fn marshal(&mut self, buffer: &mut [u8]) -> anyhow::Result<usize> {
+    fn marshal(&mut self, buffer: &mut [u8]) -> Result<usize, RmuError> {
         self.header.marshal(buffer)
     }
 
-    fn unmarshal(buffer: &[u8]) -> anyhow::Result<Self::Output> {
+    fn unmarshal(buffer: &[u8]) -> Result<Self::Output, RmuError> {
         let header = ResponseHeader::unmarshal(buffer)?;
         Ok(Self { header })
     }
@@ -48,17 +41,15 @@ impl MessageOperation for GetIdResponse {
 }
 
 impl TryFrom<ResponseHeader> for GetIdResponse {
-    type Error = anyhow::Error;
+    type Error = RmuError;
 
     fn try_from(value: ResponseHeader) -> Result<Self, Self::Error> {
         let msgcode: MessageCode = value.code.try_into()?;
         if msgcode != MessageCode::GetId {
-            return Err(anyhow::anyhow!(
-                "{}:{} message type mismatch {}",
-                file!(),
-                line!(),
-                value.code
-            ));
+            return Err(RmuError::CodeMismatch {
+                expect: MessageCode::GetId as u16,
+                got: value.code,
+            });
         }
 
         Ok(Self { header: value })
@@ -75,7 +66,7 @@ impl From<MessageBuilder<ResponseHeader>> for MessageBuilder<GetIdResponse> {
 }
 
 impl MessageBuilderOperation for GetIdResponse {
-    fn finalize(self) -> anyhow::Result<Self> {
+    fn finalize(self) -> Result<Self, RmuError> {
         Ok(self)
     }
 }
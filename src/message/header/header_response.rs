@@ -1,6 +1,5 @@
-use std::usize;
-
-use crate::message::MessageHeaderOperation;
+use crate::error::RmuError;
+use crate::message::{check_len, read_bytes, MessageHeaderOperation};
 use crate::message_builder::{MessageBuilder, MessageBuilderOperation};
 use crate::message_code::MessageCode;
 
@@ -124,7 +123,8 @@ impl MessageHeaderOperation for ResponseHeader {
         28
     }
 
-    fn marshal(&mut self, buffer: &mut [u8]) -> anyhow::Result<usize> {
+    fn marshal(&mut self, buffer: &mut [u8]) -> Result<usize, RmuError> {
+        check_len(buffer, self.wire_size())?;
         buffer[0..6].copy_from_slice(&self.destination_address[..]);
         buffer[6..12].copy_from_slice(&self.source_address[..]);
         buffer[12..14].copy_from_slice(&self.ether_type.to_be_bytes());
@@ -141,18 +141,19 @@ impl MessageHeaderOperation for ResponseHeader {
         Ok(self.wire_size())
     }
 
-    fn unmarshal(buffer: &[u8]) -> anyhow::Result<Self::Output> {
-        let destination_address: [u8; 6] = buffer[0..6].try_into()?;
-        let source_address: [u8; 6] = buffer[6..12].try_into()?;
-        let ether_type = u16::from_be_bytes(buffer[12..14].try_into().unwrap());
+    fn unmarshal(buffer: &[u8]) -> Result<Self::Output, RmuError> {
+        check_len(buffer, 28)?;
+        let destination_address: [u8; 6] = read_bytes(buffer, 0)?;
+        let source_address: [u8; 6] = read_bytes(buffer, 6)?;
+        let ether_type = u16::from_be_bytes(read_bytes(buffer, 12)?);
         let device_id = buffer[16] & 0b00011111;
-        let dsa_code = (buffer[17] & 0x06) | (buffer[18] & 0x10 >> 4);
+        let dsa_code = (buffer[17] & 0x06) | ((buffer[18] & 0x10) >> 4);
         let priority = (buffer[18] & 0b11100000) >> 5;
         let sequence_number = buffer[19];
-        let length_type = u16::from_be_bytes(buffer[20..22].try_into().unwrap());
-        let format = u16::from_be_bytes(buffer[22..24].try_into().unwrap());
-        let product_number = u16::from_be_bytes(buffer[24..26].try_into().unwrap());
-        let code = u16::from_be_bytes(buffer[26..28].try_into().unwrap());
+        let length_type = u16::from_be_bytes(read_bytes(buffer, 20)?);
+        let format = u16::from_be_bytes(read_bytes(buffer, 22)?);
+        let product_number = u16::from_be_bytes(read_bytes(buffer, 24)?);
+        let code = u16::from_be_bytes(read_bytes(buffer, 26)?);
 
         Ok(Self {
             destination_address,
@@ -169,8 +170,8 @@ impl MessageHeaderOperation for ResponseHeader {
         })
     }
 
-    fn message_code(&self) -> MessageCode {
-        self.code.try_into().unwrap()
+    fn message_code(&self) -> Result<MessageCode, RmuError> {
+        self.code.try_into()
     }
 }
 
@@ -232,7 +233,7 @@ impl MessageBuilder<ResponseHeader> {
 }
 
 impl MessageBuilderOperation for ResponseHeader {
-    fn finalize(self) -> anyhow::Result<Self> {
+    fn finalize(self) -> Result<Self, RmuError> {
         Ok(self)
     }
 }
@@ -1,3 +1,5 @@
+use crate::error::RmuError;
+
 mod register_op_request;
 mod register_op_response;
 mod register_request;
@@ -5,6 +7,7 @@ mod register_response;
 
 pub use register_op_request::RegOpRequest;
 pub use register_op_request::RegOpRequestList;
+pub use register_op_request::MAX_REGOPS_PER_FRAME;
 pub use register_request::RegisterRequest;
 
 #[allow(unused_imports)]
@@ -31,53 +34,38 @@ enum WaitOnBitOpCode {
 }
 
 impl TryFrom<u8> for OpType {
-    type Error = anyhow::Error;
+    type Error = RmuError;
 
     fn try_from(value: u8) -> Result<Self, Self::Error> {
         match value {
             0x0 => Ok(OpType::ReadWrite),
             0x1 => Ok(OpType::WaitOnBit),
             0xF => Ok(OpType::EndOfList),
-            _ => Err(anyhow::anyhow!(
-                "{}:{} unkown optype:{}",
-                file!(),
-                line!(),
-                value
-            )),
+            _ => Err(RmuError::BadOpType(value)),
         }
     }
 }
 
 impl TryFrom<u8> for ReadWriteOpCode {
-    type Error = anyhow::Error;
+    type Error = RmuError;
 
     fn try_from(value: u8) -> Result<Self, Self::Error> {
         match value {
             0x01 => Ok(ReadWriteOpCode::Write),
             0x02 => Ok(ReadWriteOpCode::Read),
-            _ => Err(anyhow::anyhow!(
-                "{}:{} unkown r/w opcode:{}",
-                file!(),
-                line!(),
-                value
-            )),
+            _ => Err(RmuError::BadOpType(value)),
         }
     }
 }
 
 impl TryFrom<u8> for WaitOnBitOpCode {
-    type Error = anyhow::Error;
+    type Error = RmuError;
 
     fn try_from(value: u8) -> Result<Self, Self::Error> {
         match value {
             0x00 => Ok(WaitOnBitOpCode::Bit0),
             0x03 => Ok(WaitOnBitOpCode::Bit1),
-            _ => Err(anyhow::anyhow!(
-                "{}:{} unkown wait_on_bit opcode:{}",
-                file!(),
-                line!(),
-                value
-            )),
+            _ => Err(RmuError::BadOpType(value)),
         }
     }
 }
@@ -1,12 +1,18 @@
 use bit_ops::bitops_u8;
 use clap::Subcommand;
 
+use crate::error::RmuError;
+use crate::message::check_len;
 use crate::message::register::OpType;
 use crate::message::register::ReadWriteOpCode;
 use crate::message::register::WaitOnBitOpCode;
 
 const END_OF_LIST: [u8; 4] = [0xFF, 0xFF, 0xFF, 0xFF];
 
+/// Ops one RwRegister request fits in a 1514 byte frame, after the 28 byte
+/// header and the end of list
+pub const MAX_REGOPS_PER_FRAME: usize = (1514 - 28) / 4 - 1;
+
 #[derive(Clone, Debug, PartialEq, Subcommand)]
 pub enum RegOpRequest {
     Read { addr: u8, reg: u8 },
@@ -16,27 +22,20 @@ pub enum RegOpRequest {
     EndOfList,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct RegOpRequestList {
     inner: Vec<RegOpRequest>,
 }
 
-impl Default for RegOpRequestList {
-    fn default() -> Self {
-        Self {
-            inner: Default::default(),
-        }
-    }
-}
-
 impl RegOpRequest {
     fn wire_size(&self) -> usize {
         4
     }
 
-    fn marshal(&self, buf: &mut [u8]) -> anyhow::Result<usize> {
-        match self {
-            &RegOpRequest::Read { addr, reg } => {
+    fn marshal(&self, buf: &mut [u8]) -> Result<usize, RmuError> {
+        check_len(buf, self.wire_size())?;
+        match *self {
+            RegOpRequest::Read { addr, reg } => {
                 // name: index 3 starting from 0, length 2
                 let addr_3_2 = bitops_u8::get_bits(addr, 2, 3);
                 let optype = OpType::ReadWrite as u8;
@@ -52,7 +51,7 @@ impl RegOpRequest {
 
                 buf[2..4].copy_from_slice(&0x0000_u16.to_be_bytes());
             }
-            &RegOpRequest::Write { addr, reg, data } => {
+            RegOpRequest::Write { addr, reg, data } => {
                 let addr_3_2 = bitops_u8::get_bits(addr, 2, 3);
                 let optype = OpType::ReadWrite as u8;
                 let opcode = ReadWriteOpCode::Write as u8;
@@ -67,7 +66,7 @@ impl RegOpRequest {
 
                 buf[2..4].copy_from_slice(&data.to_be_bytes());
             }
-            &RegOpRequest::WaitOnBit0 { addr, reg, bit } => {
+            RegOpRequest::WaitOnBit0 { addr, reg, bit } => {
                 let addr_3_2 = bitops_u8::get_bits(addr, 2, 3);
                 let optype = OpType::WaitOnBit as u8;
                 let opcode = WaitOnBitOpCode::Bit0 as u8;
@@ -85,7 +84,7 @@ impl RegOpRequest {
 
                 buf[3] = 0x00;
             }
-            &RegOpRequest::WaitOnBit1 { addr, reg, bit } => {
+            RegOpRequest::WaitOnBit1 { addr, reg, bit } => {
                 let addr_3_2 = bitops_u8::get_bits(addr, 2, 3);
                 let optype = OpType::WaitOnBit as u8;
                 let opcode = WaitOnBitOpCode::Bit1 as u8;
@@ -103,15 +102,16 @@ impl RegOpRequest {
 
                 buf[3] = 0x00;
             }
-            &RegOpRequest::EndOfList => {
-                buf.copy_from_slice(&END_OF_LIST);
+            RegOpRequest::EndOfList => {
+                buf[..4].copy_from_slice(&END_OF_LIST);
             }
         }
 
         Ok(self.wire_size())
     }
 
-    fn unmarshal(buf: &[u8]) -> anyhow::Result<Self> {
+    fn unmarshal(buf: &[u8]) -> Result<Self, RmuError> {
+        check_len(buf, 4)?;
         let optype = bitops_u8::get_bits(buf[0], 4, 4);
         let opcode = bitops_u8::get_bits(buf[0], 2, 2);
         let addr_3_2 = bitops_u8::get_bits(buf[0], 2, 0);
@@ -122,7 +122,7 @@ impl RegOpRequest {
         match optype.try_into() {
             // read/write
             Ok(OpType::ReadWrite) => {
-                let data = u16::from_be_bytes(buf[2..4].try_into().unwrap());
+                let data = u16::from_be_bytes([buf[2], buf[3]]);
                 match opcode.try_into() {
                     Ok(ReadWriteOpCode::Write) => Ok(RegOpRequest::Write { addr, reg, data }),
                     Ok(ReadWriteOpCode::Read) => Ok(RegOpRequest::Read { addr, reg }),
@@ -130,8 +130,8 @@ impl RegOpRequest {
                 }
             }
             Ok(OpType::WaitOnBit) => {
+                // buf[3] is reserved in a request
                 let bit = bitops_u8::get_bits(buf[2], 4, 0);
-                assert_eq!(buf[3], 0x00);
 
                 match opcode.try_into() {
                     Ok(WaitOnBitOpCode::Bit0) => Ok(RegOpRequest::WaitOnBit0 { addr, reg, bit }),
@@ -154,12 +154,41 @@ impl RegOpRequestList {
         self.inner.push(regop);
     }
 
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// Consecutive lists of at most `max` ops, at least one even if empty
+    pub fn split(&self, max: usize) -> Vec<RegOpRequestList> {
+        if self.inner.is_empty() {
+            return vec![Self::new()];
+        }
+        self.inner
+            .chunks(max.max(1))
+            .map(|ops| Self {
+                inner: ops.to_vec(),
+            })
+            .collect()
+    }
+
     pub fn wire_size(&self) -> usize {
         // +1 for end_of_list
         (self.inner.len() + 1) * 4
     }
 
-    pub fn marshal(&self, buf: &mut [u8]) -> anyhow::Result<usize> {
+    /// Fails with `RmuError::TooManyRegOps` beyond `MAX_REGOPS_PER_FRAME`
+    pub fn marshal(&self, buf: &mut [u8]) -> Result<usize, RmuError> {
+        if self.inner.len() > MAX_REGOPS_PER_FRAME {
+            return Err(RmuError::TooManyRegOps {
+                count: self.inner.len(),
+                max: MAX_REGOPS_PER_FRAME,
+            });
+        }
+        check_len(buf, self.wire_size())?;
         let mut offset = 0;
         for op in &self.inner {
             offset += op.marshal(&mut buf[offset..])?;
@@ -169,7 +198,7 @@ impl RegOpRequestList {
         Ok(self.wire_size())
     }
 
-    pub fn unmarshal(buf: &[u8]) -> anyhow::Result<RegOpRequestList> {
+    pub fn unmarshal(buf: &[u8]) -> Result<RegOpRequestList, RmuError> {
         let mut reqlist = RegOpRequestList::new();
         let len = buf.len();
         let mut offset = 0;
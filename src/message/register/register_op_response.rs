@@ -2,8 +2,12 @@ use core::fmt;
 
 use bit_ops::bitops_u8;
 
+use crate::error::RmuError;
+use crate::message::check_len;
 use crate::message::register::OpType;
 use crate::message::register::ReadWriteOpCode;
+use crate::message::register::RegOpRequest;
+use crate::message::register::RegOpRequestList;
 use crate::message::register::WaitOnBitOpCode;
 
 const END_OF_LIST: [u8; 4] = [0xFF, 0xFF, 0xFF, 0xFF];
@@ -35,27 +39,20 @@ pub enum RegOpResponse {
     EndOfList,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct RegOpResponseList {
     inner: Vec<RegOpResponse>,
 }
 
-impl Default for RegOpResponseList {
-    fn default() -> Self {
-        Self {
-            inner: Default::default(),
-        }
-    }
-}
-
 impl RegOpResponse {
     fn wire_size(&self) -> usize {
         4
     }
 
-    fn marshal(&self, buf: &mut [u8]) -> anyhow::Result<usize> {
-        match self {
-            &RegOpResponse::Read { addr, reg, data } => {
+    fn marshal(&self, buf: &mut [u8]) -> Result<usize, RmuError> {
+        check_len(buf, self.wire_size())?;
+        match *self {
+            RegOpResponse::Read { addr, reg, data } => {
                 // name: index 3 starting from 0, length 2
                 let addr_3_2 = bitops_u8::get_bits(addr, 2, 3);
                 let optype = OpType::ReadWrite as u8;
@@ -71,7 +68,7 @@ impl RegOpResponse {
 
                 buf[2..4].copy_from_slice(&data.to_be_bytes());
             }
-            &RegOpResponse::Write { addr, reg, data } => {
+            RegOpResponse::Write { addr, reg, data } => {
                 let addr_3_2 = bitops_u8::get_bits(addr, 2, 3);
                 let optype = OpType::ReadWrite as u8;
                 let opcode = ReadWriteOpCode::Write as u8;
@@ -86,7 +83,7 @@ impl RegOpResponse {
 
                 buf[2..4].copy_from_slice(&data.to_be_bytes());
             }
-            &RegOpResponse::WaitOnBit1 {
+            RegOpResponse::WaitOnBit1 {
                 addr,
                 reg,
                 bit,
@@ -109,7 +106,7 @@ impl RegOpResponse {
 
                 buf[3] = result;
             }
-            &RegOpResponse::WaitOnBit0 {
+            RegOpResponse::WaitOnBit0 {
                 addr,
                 reg,
                 bit,
@@ -132,13 +129,14 @@ impl RegOpResponse {
 
                 buf[3] = result;
             }
-            &RegOpResponse::EndOfList => buf.copy_from_slice(&END_OF_LIST),
+            RegOpResponse::EndOfList => buf[..4].copy_from_slice(&END_OF_LIST),
         }
 
         Ok(self.wire_size())
     }
 
-    fn unmarshal(buf: &[u8]) -> anyhow::Result<Self> {
+    fn unmarshal(buf: &[u8]) -> Result<Self, RmuError> {
+        check_len(buf, 4)?;
         let optype = bitops_u8::get_bits(buf[0], 4, 4);
         let opcode = bitops_u8::get_bits(buf[0], 2, 2);
         let addr_3_2 = bitops_u8::get_bits(buf[0], 2, 0);
@@ -148,7 +146,7 @@ impl RegOpResponse {
 
         match optype.try_into() {
             Ok(OpType::ReadWrite) => {
-                let data = u16::from_be_bytes(buf[2..4].try_into().unwrap());
+                let data = u16::from_be_bytes([buf[2], buf[3]]);
                 match opcode.try_into() {
                     Ok(ReadWriteOpCode::Write) => Ok(RegOpResponse::Write { addr, reg, data }),
                     Ok(ReadWriteOpCode::Read) => Ok(RegOpResponse::Read { addr, reg, data }),
@@ -185,22 +183,84 @@ impl RegOpResponseList {
         Default::default()
     }
 
+    pub fn add_regop(&mut self, regop: RegOpResponse) {
+        self.inner.push(regop);
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// Whether these are the answers to all of `count` ops, a device stops
+    /// at a WaitOnBit that did not settle
+    pub fn ran_all(&self, count: usize) -> bool {
+        let unsettled = matches!(
+            self.inner.last(),
+            Some(RegOpResponse::WaitOnBit0 { result, .. } | RegOpResponse::WaitOnBit1 { result, .. })
+                if *result != 0
+        );
+        self.inner.len() == count && !unsettled
+    }
+
+    /// Whether these answer the ops of `req`, in order and op for op, up to
+    /// where a device stops at a WaitOnBit that did not settle
+    pub fn answers(&self, req: &RegOpRequestList) -> bool {
+        let echoed = self
+            .inner
+            .iter()
+            .zip(req.as_ref())
+            .all(|(resp, req)| match (resp, req) {
+                (RegOpResponse::Read { addr, reg, .. }, RegOpRequest::Read { addr: a, reg: r })
+                | (
+                    RegOpResponse::Write { addr, reg, .. },
+                    RegOpRequest::Write {
+                        addr: a, reg: r, ..
+                    },
+                )
+                | (
+                    RegOpResponse::WaitOnBit0 { addr, reg, .. },
+                    RegOpRequest::WaitOnBit0 {
+                        addr: a, reg: r, ..
+                    },
+                )
+                | (
+                    RegOpResponse::WaitOnBit1 { addr, reg, .. },
+                    RegOpRequest::WaitOnBit1 {
+                        addr: a, reg: r, ..
+                    },
+                ) => addr == a && reg == r,
+                _ => false,
+            });
+        let len = self.inner.len();
+        echoed && (len == req.len() || len < req.len() && !self.ran_all(len))
+    }
+
+    /// Move the ops of `other` behind the ones of `self`
+    pub fn append(&mut self, mut other: RegOpResponseList) {
+        self.inner.append(&mut other.inner);
+    }
+
     pub fn wire_size(&self) -> usize {
         // +1 for end_of_list
         (self.inner.len() + 1) * 4
     }
 
-    pub fn marshal(&self, buf: &mut [u8]) -> anyhow::Result<usize> {
+    pub fn marshal(&self, buf: &mut [u8]) -> Result<usize, RmuError> {
+        check_len(buf, self.wire_size())?;
         let mut offset = 0;
         for regop in &self.inner {
             offset += regop.marshal(&mut buf[offset..])?;
         }
 
-        buf.copy_from_slice(&END_OF_LIST);
+        buf[offset..offset + 4].copy_from_slice(&END_OF_LIST);
         Ok(self.wire_size())
     }
 
-    pub fn unmarshal(buf: &[u8]) -> anyhow::Result<Self> {
+    pub fn unmarshal(buf: &[u8]) -> Result<Self, RmuError> {
         let len = buf.len();
         let mut offset = 0;
         let mut resplist = RegOpResponseList::new();
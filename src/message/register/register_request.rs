@@ -1,4 +1,4 @@
-use crate::message::MessageHeaderOperation;
+use crate::message::{check_len, MessageHeaderOperation};
 use crate::message::{header::RequestHeader, MessageOperation};
 use crate::message_builder::MessageBuilder;
 use crate::message_builder::MessageBuilderOperation;
@@ -7,6 +7,7 @@ use crate::message_code::MessageCode;
 use crate::message::register::RegOpRequestList;
 
 use super::RegOpRequest;
+use crate::error::RmuError;
 
 const CODE: MessageCode = MessageCode::RwRegister;
 
@@ -40,15 +41,16 @@ impl MessageOperation for RegisterRequest {
         self.header.wire_size() + self.regops.wire_size()
     }
 
-    fn marshal(&mut self, buffer: &mut [u8]) -> anyhow::Result<usize> {
+    fn marshal(&mut self, buffer: &mut [u8]) -> Result<usize, RmuError> {
+        check_len(buffer, self.wire_size())?;
         let (hbuf, pbuf) = buffer.split_at_mut(self.header.wire_size());
         let mut size = self.header.marshal(hbuf)?;
-        size = size + self.regops.marshal(pbuf)?;
+        size += self.regops.marshal(pbuf)?;
 
         Ok(size)
     }
 
-    fn unmarshal(buffer: &[u8]) -> anyhow::Result<Self::Output> {
+    fn unmarshal(buffer: &[u8]) -> Result<Self::Output, RmuError> {
         let header = RequestHeader::unmarshal(buffer)?;
         let (_, pbuf) = buffer.split_at(header.wire_size());
         let regops = RegOpRequestList::unmarshal(pbuf)?;
@@ -66,17 +68,15 @@ impl MessageOperation for RegisterRequest {
 }
 
 impl TryFrom<RequestHeader> for RegisterRequest {
-    type Error = anyhow::Error;
+    type Error = RmuError;
 
     fn try_from(value: RequestHeader) -> Result<Self, Self::Error> {
         let code: MessageCode = value.code.try_into()?;
         if code != CODE {
-            return Err(anyhow::anyhow!(
-                "{}:{} code mismatch {}",
-                file!(),
-                line!(),
-                value.code
-            ));
+            return Err(RmuError::CodeMismatch {
+                expect: CODE as u16,
+                got: value.code,
+            });
         }
 
         Ok(Self {
@@ -108,7 +108,7 @@ impl From<MessageBuilder<RequestHeader>> for MessageBuilder<RegisterRequest> {
 }
 
 impl MessageBuilderOperation for RegisterRequest {
-    fn finalize(mut self) -> anyhow::Result<Self> {
+    fn finalize(mut self) -> Result<Self, RmuError> {
         let len = self.header.length_type() + self.regops.wire_size() as u16;
         self.header.set_length_type(len);
         Ok(self)
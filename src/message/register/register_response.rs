@@ -1,10 +1,11 @@
 use crate::{
-    message::{header::ResponseHeader, MessageHeaderOperation, MessageOperation},
+    message::{check_len, header::ResponseHeader, MessageHeaderOperation, MessageOperation},
     message_builder::{MessageBuilder, MessageBuilderOperation},
     message_code::MessageCode,
 };
 
-use super::RegOpResponseList;
+use super::{RegOpResponse, RegOpResponseList};
+use crate::error::RmuError;
 
 const CODE: MessageCode = MessageCode::RwRegister;
 
@@ -38,7 +39,8 @@ impl MessageOperation for RegisterResponse {
         self.header.wire_size() + self.regops.wire_size()
     }
 
-    fn marshal(&mut self, buffer: &mut [u8]) -> anyhow::Result<usize> {
+    fn marshal(&mut self, buffer: &mut [u8]) -> Result<usize, RmuError> {
+        check_len(buffer, self.wire_size())?;
         let (hbuf, pbuf) = buffer.split_at_mut(self.header.wire_size());
         let mut size = self.header.marshal(hbuf)?;
         size += self.regops.marshal(pbuf)?;
@@ -46,7 +48,7 @@ impl MessageOperation for RegisterResponse {
         Ok(size)
     }
 
-    fn unmarshal(buffer: &[u8]) -> anyhow::Result<Self::Output> {
+    fn unmarshal(buffer: &[u8]) -> Result<Self::Output, RmuError> {
         let header = ResponseHeader::unmarshal(buffer)?;
         let (_, pbuf) = buffer.split_at(header.wire_size());
         let regops = RegOpResponseList::unmarshal(pbuf)?;
@@ -64,17 +66,15 @@ impl MessageOperation for RegisterResponse {
 }
 
 impl TryFrom<ResponseHeader> for RegisterResponse {
-    type Error = anyhow::Error;
+    type Error = RmuError;
 
     fn try_from(value: ResponseHeader) -> Result<Self, Self::Error> {
         let code: MessageCode = value.code.try_into()?;
         if code != CODE {
-            return Err(anyhow::anyhow!(
-                "{}:{} code mismatch {}",
-                file!(),
-                line!(),
-                value.code
-            ));
+            return Err(RmuError::CodeMismatch {
+                expect: CODE as u16,
+                got: value.code,
+            });
         }
 
         Ok(Self {
@@ -84,6 +84,18 @@ impl TryFrom<ResponseHeader> for RegisterResponse {
     }
 }
 
+impl MessageBuilder<RegisterResponse> {
+    pub fn add_regop(mut self, regop: RegOpResponse) -> Self {
+        self.inner.regops.add_regop(regop);
+        self
+    }
+
+    pub fn regops(mut self, regops: RegOpResponseList) -> Self {
+        self.inner.regops = regops;
+        self
+    }
+}
+
 impl From<MessageBuilder<ResponseHeader>> for MessageBuilder<RegisterResponse> {
     fn from(value: MessageBuilder<ResponseHeader>) -> Self {
         let header = value.code(CODE as u16).build().unwrap();
@@ -94,7 +106,7 @@ impl From<MessageBuilder<ResponseHeader>> for MessageBuilder<RegisterResponse> {
 }
 
 impl MessageBuilderOperation for RegisterResponse {
-    fn finalize(mut self) -> anyhow::Result<Self> {
+    fn finalize(mut self) -> Result<Self, RmuError> {
         let len = self.header.length_type() + self.regops.wire_size() as u16;
         self.header.set_length_type(len);
         Ok(self)
@@ -1,10 +1,355 @@
+use std::fmt;
+
+use mac_address::MacAddress;
+
+use crate::error::RmuError;
+use crate::message::{check_len, read_bytes};
+use crate::message::{header::ResponseHeader, MessageHeaderOperation, MessageOperation};
+use crate::message_builder::{MessageBuilder, MessageBuilderOperation};
+use crate::message_code::MessageCode;
+
+// Functional_Specification.pdf 2.11.5 Error Response
+#[derive(Debug)]
 pub struct ResponseError {
+    header: ResponseHeader,
     request_format: u16,
     request_code: u16,
 }
 
+#[derive(Debug)]
 pub struct ResponseErrorExt {
+    header: ResponseHeader,
     request_format: u16,
     request_code: u16,
     error_code: u16,
 }
+
+/// Request rejected by the device, decoded from either error response
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeviceError {
+    pub mac: [u8; 6],
+    pub devid: u8,
+    pub request_format: u16,
+    pub request_code: u16,
+    pub error_code: Option<u16>,
+}
+
+impl ResponseError {
+    pub fn request_format(&self) -> u16 {
+        self.request_format
+    }
+    pub fn set_request_format(&mut self, val: u16) -> &mut Self {
+        self.request_format = val;
+        self
+    }
+
+    pub fn request_code(&self) -> u16 {
+        self.request_code
+    }
+    pub fn set_request_code(&mut self, val: u16) -> &mut Self {
+        self.request_code = val;
+        self
+    }
+
+    pub fn payload_wire_size(&self) -> usize {
+        4
+    }
+}
+
+impl ResponseErrorExt {
+    pub fn request_format(&self) -> u16 {
+        self.request_format
+    }
+    pub fn set_request_format(&mut self, val: u16) -> &mut Self {
+        self.request_format = val;
+        self
+    }
+
+    pub fn request_code(&self) -> u16 {
+        self.request_code
+    }
+    pub fn set_request_code(&mut self, val: u16) -> &mut Self {
+        self.request_code = val;
+        self
+    }
+
+    pub fn error_code(&self) -> u16 {
+        self.error_code
+    }
+    pub fn set_error_code(&mut self, val: u16) -> &mut Self {
+        self.error_code = val;
+        self
+    }
+
+    pub fn payload_wire_size(&self) -> usize {
+        6
+    }
+}
+
+impl Default for ResponseError {
+    fn default() -> Self {
+        Self {
+            header: ResponseHeader {
+                code: MessageCode::ErrorResponse as u16,
+                ..Default::default()
+            },
+            request_format: 0,
+            request_code: 0,
+        }
+    }
+}
+
+impl Default for ResponseErrorExt {
+    fn default() -> Self {
+        Self {
+            header: ResponseHeader {
+                code: MessageCode::ErrorResponseEx as u16,
+                ..Default::default()
+            },
+            request_format: 0,
+            request_code: 0,
+            error_code: 0,
+        }
+    }
+}
+
+impl MessageOperation for ResponseError {
+    type Output = Self;
+    type Header = ResponseHeader;
+
+    fn message_code() -> MessageCode {
+        MessageCode::ErrorResponse
+    }
+
+    fn wire_size(&self) -> usize {
+        self.header.wire_size() + self.payload_wire_size()
+    }
+
+    fn marshal(&mut self, buffer: &mut [u8]) -> Result<usize, RmuError> {
+        check_len(buffer, self.wire_size())?;
+        let (hbuf, pbuf) = buffer.split_at_mut(self.header.wire_size());
+        self.header.marshal(hbuf)?;
+        pbuf[0..2].copy_from_slice(&self.request_format.to_be_bytes());
+        pbuf[2..4].copy_from_slice(&self.request_code.to_be_bytes());
+        Ok(self.wire_size())
+    }
+
+    fn unmarshal(buffer: &[u8]) -> Result<Self::Output, RmuError> {
+        let header = ResponseHeader::unmarshal(buffer)?;
+        let (_, pbuf) = buffer.split_at(header.wire_size());
+
+        Ok(Self {
+            header,
+            request_format: u16::from_be_bytes(read_bytes(pbuf, 0)?),
+            request_code: u16::from_be_bytes(read_bytes(pbuf, 2)?),
+        })
+    }
+
+    fn header(&self) -> &Self::Header {
+        &self.header
+    }
+
+    fn header_mut(&mut self) -> &mut Self::Header {
+        &mut self.header
+    }
+}
+
+impl MessageOperation for ResponseErrorExt {
+    type Output = Self;
+    type Header = ResponseHeader;
+
+    fn message_code() -> MessageCode {
+        MessageCode::ErrorResponseEx
+    }
+
+    fn wire_size(&self) -> usize {
+        self.header.wire_size() + self.payload_wire_size()
+    }
+
+    fn marshal(&mut self, buffer: &mut [u8]) -> Result<usize, RmuError> {
+        check_len(buffer, self.wire_size())?;
+        let (hbuf, pbuf) = buffer.split_at_mut(self.header.wire_size());
+        self.header.marshal(hbuf)?;
+        pbuf[0..2].copy_from_slice(&self.request_format.to_be_bytes());
+        pbuf[2..4].copy_from_slice(&self.request_code.to_be_bytes());
+        pbuf[4..6].copy_from_slice(&self.error_code.to_be_bytes());
+        Ok(self.wire_size())
+    }
+
+    fn unmarshal(buffer: &[u8]) -> Result<Self::Output, RmuError> {
+        let header = ResponseHeader::unmarshal(buffer)?;
+        let (_, pbuf) = buffer.split_at(header.wire_size());
+
+        Ok(Self {
+            header,
+            request_format: u16::from_be_bytes(read_bytes(pbuf, 0)?),
+            request_code: u16::from_be_bytes(read_bytes(pbuf, 2)?),
+            error_code: u16::from_be_bytes(read_bytes(pbuf, 4)?),
+        })
+    }
+
+    fn header(&self) -> &Self::Header {
+        &self.header
+    }
+
+    fn header_mut(&mut self) -> &mut Self::Header {
+        &mut self.header
+    }
+}
+
+impl TryFrom<ResponseHeader> for ResponseError {
+    type Error = RmuError;
+
+    fn try_from(value: ResponseHeader) -> Result<Self, Self::Error> {
+        let code: MessageCode = value.code.try_into()?;
+        if code != MessageCode::ErrorResponse {
+            return Err(RmuError::CodeMismatch {
+                expect: MessageCode::ErrorResponse as u16,
+                got: value.code,
+            });
+        }
+
+        Ok(Self {
+            header: value,
+            request_format: 0,
+            request_code: 0,
+        })
+    }
+}
+
+impl TryFrom<ResponseHeader> for ResponseErrorExt {
+    type Error = RmuError;
+
+    fn try_from(value: ResponseHeader) -> Result<Self, Self::Error> {
+        let code: MessageCode = value.code.try_into()?;
+        if code != MessageCode::ErrorResponseEx {
+            return Err(RmuError::CodeMismatch {
+                expect: MessageCode::ErrorResponseEx as u16,
+                got: value.code,
+            });
+        }
+
+        Ok(Self {
+            header: value,
+            request_format: 0,
+            request_code: 0,
+            error_code: 0,
+        })
+    }
+}
+
+impl MessageBuilder<ResponseError> {
+    pub fn request_format(mut self, val: u16) -> Self {
+        self.inner.set_request_format(val);
+        self
+    }
+
+    pub fn request_code(mut self, val: u16) -> Self {
+        self.inner.set_request_code(val);
+        self
+    }
+}
+
+impl MessageBuilder<ResponseErrorExt> {
+    pub fn request_format(mut self, val: u16) -> Self {
+        self.inner.set_request_format(val);
+        self
+    }
+
+    pub fn request_code(mut self, val: u16) -> Self {
+        self.inner.set_request_code(val);
+        self
+    }
+
+    pub fn error_code(mut self, val: u16) -> Self {
+        self.inner.set_error_code(val);
+        self
+    }
+}
+
+impl From<MessageBuilder<ResponseHeader>> for MessageBuilder<ResponseError> {
+    fn from(value: MessageBuilder<ResponseHeader>) -> Self {
+        let header = value
+            .code(MessageCode::ErrorResponse as u16)
+            .build()
+            .unwrap();
+        Self {
+            inner: header.try_into().unwrap(),
+        }
+    }
+}
+
+impl From<MessageBuilder<ResponseHeader>> for MessageBuilder<ResponseErrorExt> {
+    fn from(value: MessageBuilder<ResponseHeader>) -> Self {
+        let header = value
+            .code(MessageCode::ErrorResponseEx as u16)
+            .build()
+            .unwrap();
+        Self {
+            inner: header.try_into().unwrap(),
+        }
+    }
+}
+
+impl MessageBuilderOperation for ResponseError {
+    fn finalize(mut self) -> Result<Self, RmuError> {
+        let len = self.header.length_type() + self.payload_wire_size() as u16;
+        self.header.set_length_type(len);
+        Ok(self)
+    }
+}
+
+impl MessageBuilderOperation for ResponseErrorExt {
+    fn finalize(mut self) -> Result<Self, RmuError> {
+        let len = self.header.length_type() + self.payload_wire_size() as u16;
+        self.header.set_length_type(len);
+        Ok(self)
+    }
+}
+
+impl From<ResponseError> for DeviceError {
+    fn from(value: ResponseError) -> Self {
+        Self {
+            mac: value.header.source_address(),
+            devid: value.header.device_id(),
+            request_format: value.request_format,
+            request_code: value.request_code,
+            error_code: None,
+        }
+    }
+}
+
+impl From<ResponseErrorExt> for DeviceError {
+    fn from(value: ResponseErrorExt) -> Self {
+        Self {
+            mac: value.header.source_address(),
+            devid: value.header.device_id(),
+            request_format: value.request_format,
+            request_code: value.request_code,
+            error_code: Some(value.error_code),
+        }
+    }
+}
+
+impl fmt::Display for DeviceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mac: MacAddress = self.mac.into();
+        write!(
+            f,
+            "mac:{} devid:0x{:02X} rejected request code:0x{:04X}",
+            mac, self.devid, self.request_code
+        )?;
+
+        if let Ok(code) = MessageCode::try_from(self.request_code) {
+            write!(f, "({:?})", code)?;
+        }
+        write!(f, " format:0x{:04X}", self.request_format)?;
+
+        match self.error_code {
+            Some(error_code) => write!(f, " error:0x{:04X}", error_code),
+            None => Ok(()),
+        }
+    }
+}
+
+impl std::error::Error for DeviceError {}
@@ -1,6 +1,5 @@
-use std::u16;
-
-use crate::message::MessageHeaderOperation;
+use crate::error::RmuError;
+use crate::message::{check_len, read_bytes, MessageHeaderOperation};
 use crate::message_builder::{MessageBuilder, MessageBuilderOperation};
 use crate::{
     message::{header::ResponseHeader, MessageOperation},
@@ -45,18 +44,19 @@ impl MessageOperation for VersionReadResponse {
         self.header.wire_size() + self.payload_wire_size()
     }
 
-    fn marshal(&mut self, buffer: &mut [u8]) -> anyhow::Result<usize> {
+    fn marshal(&mut self, buffer: &mut [u8]) -> Result<usize, RmuError> {
+        check_len(buffer, self.wire_size())?;
         let (hbuf, pbuf) = buffer.split_at_mut(self.header.wire_size());
         self.header.marshal(hbuf)?;
         pbuf[0..4].copy_from_slice(&self.crc32.to_be_bytes());
         Ok(self.wire_size())
     }
 
-    fn unmarshal(buffer: &[u8]) -> anyhow::Result<Self::Output> {
+    fn unmarshal(buffer: &[u8]) -> Result<Self::Output, RmuError> {
         let header = ResponseHeader::unmarshal(buffer)?;
         let (_, pbuf) = buffer.split_at(header.wire_size());
-        // @todo: 1. check code 2. check with type_length
-        let crc32 = u32::from_be_bytes(pbuf[..4].try_into().unwrap());
+        // @todo: check with type_length
+        let crc32 = u32::from_be_bytes(read_bytes(pbuf, 0)?);
 
         Ok(Self { header, crc32 })
     }
@@ -71,17 +71,15 @@ impl MessageOperation for VersionReadResponse {
 }
 
 impl TryFrom<ResponseHeader> for VersionReadResponse {
-    type Error = anyhow::Error;
+    type Error = RmuError;
 
     fn try_from(value: ResponseHeader) -> Result<Self, Self::Error> {
         let code: MessageCode = value.code.try_into()?;
         if code != MessageCode::VersionRead {
-            return Err(anyhow::anyhow!(
-                "{}:{} message type mismatch {}",
-                file!(),
-                line!(),
-                value.code
-            ));
+            return Err(RmuError::CodeMismatch {
+                expect: MessageCode::VersionRead as u16,
+                got: value.code,
+            });
         }
 
         Ok(Self {
@@ -91,6 +89,13 @@ impl TryFrom<ResponseHeader> for VersionReadResponse {
     }
 }
 
+impl MessageBuilder<VersionReadResponse> {
+    pub fn crc32(mut self, val: u32) -> Self {
+        self.inner.crc32 = val;
+        self
+    }
+}
+
 impl From<MessageBuilder<ResponseHeader>> for MessageBuilder<VersionReadResponse> {
     fn from(value: MessageBuilder<ResponseHeader>) -> Self {
         let header = value.code(CODE as u16).build().unwrap();
@@ -101,7 +106,7 @@ impl From<MessageBuilder<ResponseHeader>> for MessageBuilder<VersionReadResponse
 }
 
 impl MessageBuilderOperation for VersionReadResponse {
-    fn finalize(mut self) -> anyhow::Result<Self> {
+    fn finalize(mut self) -> Result<Self, RmuError> {
         let len = self.header.length_type() + self.payload_wire_size() as u16;
         self.header.set_length_type(len);
         Ok(self)
@@ -1,3 +1,5 @@
+use crate::error::RmuError;
+
 #[derive(Debug)]
 pub struct MessageBuilder<T> {
     pub inner: T,
@@ -13,11 +15,20 @@ where
         }
     }
 
-    pub fn build(self) -> anyhow::Result<T> {
+    pub fn build(self) -> Result<T, RmuError> {
         self.inner.finalize()
     }
 }
 
+impl<T> Default for MessageBuilder<T>
+where
+    T: Default + MessageBuilderOperation,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 pub trait MessageBuilderOperation: Sized {
-    fn finalize(self) -> anyhow::Result<Self>;
+    fn finalize(self) -> Result<Self, RmuError>;
 }
@@ -1,3 +1,5 @@
+use crate::error::RmuError;
+
 #[derive(PartialEq, Debug)]
 pub enum MessageCode {
     GetId = 0x0000,
@@ -10,7 +12,7 @@ pub enum MessageCode {
 }
 
 impl TryFrom<u16> for MessageCode {
-    type Error = anyhow::Error;
+    type Error = RmuError;
 
     fn try_from(value: u16) -> Result<Self, Self::Error> {
         match value {
@@ -21,12 +23,7 @@ impl TryFrom<u16> for MessageCode {
             0xF293 => Ok(MessageCode::FwVersionGet),
             0xFFFE => Ok(MessageCode::ErrorResponseEx),
             0xFFFF => Ok(MessageCode::ErrorResponse),
-            _ => Err(anyhow::anyhow!(
-                "{}:{} Unkown type 0x{:04X}",
-                file!(),
-                line!(),
-                value
-            )),
+            _ => Err(RmuError::UnknownCode(value)),
         }
     }
 }
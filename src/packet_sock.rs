@@ -1,9 +1,32 @@
+use std::io::{self, ErrorKind, Read, Write};
+use std::mem::MaybeUninit;
+use std::os::fd::AsRawFd;
+use std::path::Path;
+use std::sync::OnceLock;
+use std::time::Instant;
+
 use anyhow::Context;
 use libc::{sockaddr_ll, sockaddr_storage, socklen_t};
+use mac_address::mac_address_by_name;
+use smol::future::FutureExt;
+use smol::Timer;
 use socket2::{Domain, Protocol, SockAddr, Socket, Type};
 
+use crate::capture::{Capture, Direction};
+use crate::transport::Transport;
+
 pub const ETH_P_RMU: libc::c_int = 0x9101;
 
+static CAPTURE: OnceLock<Capture> = OnceLock::new();
+
+/// Record the frames of every `PacketSock` opened from now on to `path`
+pub fn capture_to(path: &Path) -> anyhow::Result<()> {
+    let capture = Capture::create(path).with_context(|| format!("create {}", path.display()))?;
+    CAPTURE
+        .set(capture)
+        .map_err(|_| anyhow::anyhow!("capture already enabled"))
+}
+
 fn ifindex_of(name: &str) -> anyhow::Result<i32> {
     if name.len() > libc::IFNAMSIZ {
         return Err(anyhow::anyhow!("ifname invalid"));
@@ -21,10 +44,18 @@ fn ifindex_of(name: &str) -> anyhow::Result<i32> {
 
 // @todo
 pub fn create_rmu_sock(interface: &str) -> anyhow::Result<smol::Async<Socket>> {
+    create_packet_sock(interface, ETH_P_RMU)
+}
+
+/// Raw socket on `interface` receiving frames of ethertype `protocol`
+fn create_packet_sock(
+    interface: &str,
+    protocol: libc::c_int,
+) -> anyhow::Result<smol::Async<Socket>> {
     let sk = Socket::new(
         Domain::from(libc::AF_PACKET),
         Type::RAW,
-        Some(Protocol::from(ETH_P_RMU.to_be())),
+        Some(Protocol::from((protocol as u16).to_be() as libc::c_int)),
     )
     .context("create raw sock fail")?;
 
@@ -33,7 +64,7 @@ pub fn create_rmu_sock(interface: &str) -> anyhow::Result<smol::Async<Socket>> {
         let mut ss: sockaddr_storage = std::mem::zeroed();
         let ssl = &mut *std::mem::transmute::<*mut sockaddr_storage, *mut sockaddr_ll>(&mut ss);
         ssl.sll_family = libc::AF_PACKET as u16;
-        ssl.sll_protocol = (ETH_P_RMU as u16).to_be();
+        ssl.sll_protocol = (protocol as u16).to_be();
         ssl.sll_ifindex = ifindex_of(interface)?;
         ssl.sll_pkttype = 0;
         ssl.sll_hatype = 0;
@@ -47,3 +78,132 @@ pub fn create_rmu_sock(interface: &str) -> anyhow::Result<smol::Async<Socket>> {
 
     Ok(smol::Async::new(sk)?)
 }
+
+/// AF_PACKET socket bound to one interface, the transport used against real hardware
+pub struct PacketSock {
+    sock: smol::Async<Socket>,
+    smac: [u8; 6],
+    // interface id in the capture file, if recording
+    capture_ifid: Option<u32>,
+}
+
+impl PacketSock {
+    pub fn open(interface: &str) -> anyhow::Result<Self> {
+        let sock = create_rmu_sock(interface)?;
+        let smac = mac_address_by_name(interface)?
+            .ok_or_else(|| anyhow::anyhow!("no mac address on {}", interface))?
+            .bytes();
+
+        let capture_ifid = match CAPTURE.get() {
+            Some(capture) => Some(capture.add_interface(interface)?),
+            None => None,
+        };
+
+        Ok(Self {
+            sock,
+            smac,
+            capture_ifid,
+        })
+    }
+
+    fn record(&self, direction: Direction, frame: &[u8]) -> io::Result<()> {
+        match (CAPTURE.get(), self.capture_ifid) {
+            (Some(capture), Some(ifid)) => capture.record(ifid, direction, frame),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Transport for PacketSock {
+    fn source_address(&self) -> [u8; 6] {
+        self.smac
+    }
+
+    async fn send(&self, frame: &[u8]) -> io::Result<usize> {
+        let sz = self.sock.write_with(|mut s| s.write(frame)).await?;
+        self.record(Direction::Outbound, &frame[..sz])?;
+        Ok(sz)
+    }
+
+    async fn recv(&self, buf: &mut [u8], deadline: Instant) -> io::Result<usize> {
+        let sz = self
+            .sock
+            .read_with(|mut s| s.read(buf))
+            .or(async {
+                Timer::at(deadline).await;
+                Err(ErrorKind::TimedOut.into())
+            })
+            .await?;
+        self.record(Direction::Inbound, &buf[..sz])?;
+        Ok(sz)
+    }
+}
+
+/// Promiscuous socket seeing every frame on an interface, sent or received
+///
+/// Unlike `PacketSock` it also gets the frames other sockets of the host send,
+/// e.g. requests of another mrmu, and those addressed to other stations.
+pub struct Sniffer {
+    sock: smol::Async<Socket>,
+    capture_ifid: Option<u32>,
+}
+
+impl Sniffer {
+    pub fn open(interface: &str) -> anyhow::Result<Self> {
+        let sock = create_packet_sock(interface, libc::ETH_P_ALL)?;
+
+        let mreq = libc::packet_mreq {
+            mr_ifindex: ifindex_of(interface)?,
+            mr_type: libc::PACKET_MR_PROMISC as u16,
+            mr_alen: 0,
+            mr_address: [0; 8],
+        };
+        // dropped again by the kernel when the socket closes
+        let ret = unsafe {
+            libc::setsockopt(
+                sock.as_raw_fd(),
+                libc::SOL_PACKET,
+                libc::PACKET_ADD_MEMBERSHIP,
+                &mreq as *const libc::packet_mreq as *const libc::c_void,
+                std::mem::size_of::<libc::packet_mreq>() as socklen_t,
+            )
+        };
+        if ret != 0 {
+            return Err(io::Error::last_os_error()).context("enable promiscuous mode fail");
+        }
+
+        let capture_ifid = match CAPTURE.get() {
+            Some(capture) => Some(capture.add_interface(interface)?),
+            None => None,
+        };
+
+        Ok(Self { sock, capture_ifid })
+    }
+
+    /// Receive the next frame, fail with `ErrorKind::TimedOut` once `deadline` passed
+    pub async fn recv(&self, buf: &mut [u8], deadline: Instant) -> io::Result<(usize, Direction)> {
+        let (sz, addr) = self
+            .sock
+            .read_with(|s| {
+                // SAFETY: recv_from only writes initialized bytes into buf
+                let uninit = unsafe { &mut *(buf as *mut [u8] as *mut [MaybeUninit<u8>]) };
+                s.recv_from(uninit)
+            })
+            .or(async {
+                Timer::at(deadline).await;
+                Err(ErrorKind::TimedOut.into())
+            })
+            .await?;
+
+        let pkttype = unsafe { (*(addr.as_ptr() as *const sockaddr_ll)).sll_pkttype };
+        let direction = match pkttype {
+            libc::PACKET_OUTGOING => Direction::Outbound,
+            _ => Direction::Inbound,
+        };
+
+        if let (Some(capture), Some(ifid)) = (CAPTURE.get(), self.capture_ifid) {
+            capture.record(ifid, direction, &buf[..sz])?;
+        }
+        Ok((sz, direction))
+    }
+}
@@ -1,18 +1,162 @@
+use std::str::FromStr;
+
 use bit_ops::bitops_u16;
+use serde::Deserialize;
+
+// field enums of the generated layouts, each variant a bitinfo_comb_flat!
+macro_rules! impl_into_bitinfo {
+    ($bitinfo: ty) => {
+        impl From<$bitinfo> for BitInfo {
+            fn from(value: $bitinfo) -> Self {
+                let comb = value as u16;
+                bitinfo_comb_deflat!(comb)
+            }
+        }
+    };
+}
+
+mod register_db;
+
+/// Typed registers, fields and settings of the builtin description
+///
+/// Generated by build.rs from registers.toml: `PortRegister`,
+/// `Global1Register` and `Global2Register` give the offsets, one enum per
+/// register named after it (`AtuData`) its fields, and one per field with
+/// named values, after register and field (`AtuOperationAtuOpValue`), the
+/// settings.
+mod layout {
+    #![allow(clippy::enum_variant_names)]
 
-mod global1_register;
-mod port_register;
+    use super::BitInfo;
+    use crate::bitinfo_comb_deflat;
+    use crate::bitinfo_comb_flat;
 
-pub use port_register::PhysicalControl;
-pub use port_register::PortRegister;
-pub use port_register::PortSTatus;
+    include!(concat!(env!("OUT_DIR"), "/registers.rs"));
+}
+
+pub use layout::*;
+pub use register_db::{load_register_db, register_db};
+pub use register_db::{Device, RegisterDb, RegisterDesc};
+
+/// SMI device address of the Global1 registers
+pub const GLOBAL1_ADDR: u8 = 0x1B;
+
+/// SMI device address of the Global2 registers
+pub const GLOBAL2_ADDR: u8 = 0x1C;
 
-#[derive(PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct BitInfo {
     pub len: u8,
     pub shift: u8,
 }
 
+/// Access type of a register or field
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, strum_macros::Display)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum Access {
+    Ro,
+    #[default]
+    Rw,
+    Wo,
+    /// Self clearing, e.g. busy bits starting an operation
+    Sc,
+}
+
+/// Named bit field of a register
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field {
+    pub name: String,
+    pub info: BitInfo,
+    pub access: Access,
+    /// Names of the field settings, by value
+    pub values: Vec<(String, u16)>,
+}
+
+impl Field {
+    pub fn get(&self, val: u16) -> u16 {
+        u16_get_bits(val, self.info)
+    }
+
+    /// Name of setting `val` if the description has one
+    pub fn value_name(&self, val: u16) -> Option<&str> {
+        self.values
+            .iter()
+            .find(|&&(_, v)| v == val)
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// Setting named `name`
+    pub fn value_of(&self, name: &str) -> Option<u16> {
+        self.values.iter().find(|(n, _)| n == name).map(|&(_, v)| v)
+    }
+}
+
+/// Register named like in the datasheet, down to a field if given
+///
+/// Written `port3.port_status`, `global1.atu_fid` or `global1.atu_op.atu_busy`:
+/// the SMI device (`portN`, `global1`, `global2`), a register of the
+/// description in use with `_` or `-`, then the optional field name.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegisterPath {
+    pub addr: u8,
+    pub reg: u8,
+    pub field: Option<Field>,
+}
+
+impl FromStr for RegisterPath {
+    type Err = String;
+
+    fn from_str(path: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = path.split('.').collect();
+        if parts.len() < 2 || parts.len() > 3 {
+            return Err(format!(
+                "{}: expect device.register[.field], e.g. port3.port_status",
+                path
+            ));
+        }
+
+        let (device, port) = match parts[0].to_lowercase().as_str() {
+            "global1" => (Device::Global1, 0),
+            "global2" => (Device::Global2, 0),
+            dev => {
+                let port = dev
+                    .strip_prefix("port")
+                    .and_then(|num| clap_num::maybe_hex::<u8>(num).ok())
+                    .filter(|&port| port < GLOBAL1_ADDR)
+                    .ok_or_else(|| {
+                        format!(
+                            "{}: no device {}, one of portN, global1, global2",
+                            path, dev
+                        )
+                    })?;
+                (Device::Port, port)
+            }
+        };
+        let desc = register_db()
+            .find(device, parts[1])
+            .map_err(|e| format!("{}: {}", path, e))?;
+
+        let field = match parts.get(2) {
+            Some(wanted) => {
+                let field = desc.fields.iter().find(|field| field.name == *wanted);
+                Some(
+                    field
+                        .cloned()
+                        .ok_or_else(|| format!("{}: no field {}", path, wanted))?,
+                )
+            }
+            None => None,
+        };
+
+        Ok(Self {
+            addr: device.smi_addr(port),
+            reg: desc.offset,
+            field,
+        })
+    }
+}
+
 #[macro_export]
 macro_rules! bitinfo_comb_flat {
     ($len: expr, $shift: expr) => {
@@ -49,7 +193,8 @@ pub fn u16_get_bits<T: Into<BitInfo>>(base: u16, opaque: T) -> u16 {
     bitops_u16::get_bits(base, info.len.into(), info.shift.into())
 }
 
+/// Replace the field in `base` by `val`, bits of `val` beyond the field are dropped
 pub fn u16_set_bits<T: Into<BitInfo>>(base: u16, val: u16, opaque: T) -> u16 {
     let info = opaque.into();
-    bitops_u16::set_bits(base, val, info.len.into(), info.shift.into())
+    bitops_u16::set_bits_exact(base, val, info.len.into(), info.shift.into())
 }
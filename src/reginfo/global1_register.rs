@@ -1 +0,0 @@
-struct AtuFid {}
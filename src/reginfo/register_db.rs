@@ -0,0 +1,315 @@
+use std::collections::BTreeMap;
+use std::io;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use anyhow::Context;
+use serde::Deserialize;
+
+use super::{u16_set_bits, Access, BitInfo, Field, GLOBAL1_ADDR, GLOBAL2_ADDR};
+use crate::chip::Feature;
+
+const BUILTIN: &str = include_str!("registers.toml");
+
+static REGISTER_DB: OnceLock<RegisterDb> = OnceLock::new();
+
+/// The registers in use, the builtin description unless `load_register_db` ran before
+pub fn register_db() -> &'static RegisterDb {
+    REGISTER_DB.get_or_init(RegisterDb::builtin)
+}
+
+/// Lay the description in `path` over the builtin one for the rest of the run
+pub fn load_register_db(path: &Path) -> anyhow::Result<()> {
+    let text = std::fs::read_to_string(path).with_context(|| format!("read {}", path.display()))?;
+    let mut db = RegisterDb::builtin();
+    db.merge(RegisterDb::parse(&text).with_context(|| format!("load {}", path.display()))?);
+    REGISTER_DB
+        .set(db)
+        .map_err(|_| anyhow::anyhow!("register description already in use"))
+}
+
+/// SMI address class of a register
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, strum_macros::Display)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum Device {
+    /// Per port, at the address of the port
+    Port,
+    Global1,
+    Global2,
+}
+
+impl Device {
+    /// SMI address of the register block, `port` only matters for `Port`
+    pub fn smi_addr(self, port: u8) -> u8 {
+        match self {
+            Device::Port => port,
+            Device::Global1 => GLOBAL1_ADDR,
+            Device::Global2 => GLOBAL2_ADDR,
+        }
+    }
+
+    /// Block at SMI address `addr`
+    pub fn of_smi_addr(addr: u8) -> Self {
+        match addr {
+            GLOBAL1_ADDR => Device::Global1,
+            GLOBAL2_ADDR => Device::Global2,
+            _ => Device::Port,
+        }
+    }
+
+    /// Name of the block at `addr` as in register paths, e.g. port3 or global1
+    pub fn path_name(addr: u8) -> String {
+        match Self::of_smi_addr(addr) {
+            Device::Port => format!("port{}", addr),
+            device => device.to_string(),
+        }
+    }
+}
+
+/// One register out of the description
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegisterDesc {
+    pub name: String,
+    /// Other names the register is found by, e.g. shorter ones
+    pub aliases: Vec<String>,
+    pub device: Device,
+    pub offset: u8,
+    pub access: Access,
+    /// Block the register belongs to if not every chip has it
+    pub feature: Option<Feature>,
+    /// Fields selecting the entry of the indirect table behind the register,
+    /// empty for a plain one
+    pub index: Vec<String>,
+    pub fields: Vec<Field>,
+}
+
+impl RegisterDesc {
+    /// Fronts an indirect table
+    ///
+    /// A read returns only the entry its index fields selected last, never the
+    /// table, so the value of such a register says nothing about the device
+    /// and is neither dumped, compared nor restored.
+    pub fn is_indirect(&self) -> bool {
+        !self.index.is_empty()
+    }
+
+    /// Bits of the fields with `access`
+    pub fn mask_of(&self, access: Access) -> u16 {
+        self.fields
+            .iter()
+            .filter(|field| field.access == access)
+            .fold(0, |mask, field| u16_set_bits(mask, 0xFFFF, field.info))
+    }
+}
+
+/// Register and field definitions, see `registers.toml` for the format
+#[derive(Debug, Clone, Default)]
+pub struct RegisterDb {
+    pub chips: Vec<String>,
+    registers: Vec<RegisterDesc>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawDb {
+    #[serde(default)]
+    chips: Vec<String>,
+    #[serde(default)]
+    register: Vec<RawRegister>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawRegister {
+    name: String,
+    #[serde(default)]
+    aliases: Vec<String>,
+    device: Device,
+    offset: u8,
+    #[serde(default)]
+    access: Access,
+    feature: Option<Feature>,
+    #[serde(default)]
+    index: Vec<String>,
+    fields: Vec<RawField>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawField {
+    name: String,
+    bits: String,
+    access: Option<Access>,
+    #[serde(default)]
+    values: BTreeMap<String, u16>,
+}
+
+fn invalid(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// `msb:lsb` or a single bit
+fn parse_bits(bits: &str) -> Option<BitInfo> {
+    let (msb, lsb) = match bits.split_once(':') {
+        Some((msb, lsb)) => (
+            msb.trim().parse::<u8>().ok()?,
+            lsb.trim().parse::<u8>().ok()?,
+        ),
+        None => {
+            let bit = bits.trim().parse::<u8>().ok()?;
+            (bit, bit)
+        }
+    };
+    if msb > 15 || lsb > msb {
+        return None;
+    }
+    Some(BitInfo {
+        len: msb - lsb + 1,
+        shift: lsb,
+    })
+}
+
+fn convert(raw: RawRegister) -> io::Result<RegisterDesc> {
+    let name = raw.name;
+    if raw.offset > 0x1F {
+        return Err(invalid(format!("register {}: offset beyond 0x1F", name)));
+    }
+
+    let mut used = 0u32;
+    let mut fields: Vec<Field> = Vec::new();
+    for field in raw.fields {
+        let info = parse_bits(&field.bits)
+            .ok_or_else(|| invalid(format!("{}.{}: bad bits {}", name, field.name, field.bits)))?;
+        let mask = ((1u32 << info.len) - 1) << info.shift;
+        if used & mask != 0 || fields.iter().any(|f| f.name == field.name) {
+            return Err(invalid(format!("{}.{}: overlaps", name, field.name)));
+        }
+        used |= mask;
+
+        if let Some((value, _)) = field.values.iter().find(|(_, &v)| v >> info.len != 0) {
+            return Err(invalid(format!(
+                "{}.{}: value {} does not fit",
+                name, field.name, value
+            )));
+        }
+        let mut values: Vec<(String, u16)> = field.values.into_iter().collect();
+        values.sort_by_key(|&(_, v)| v);
+
+        fields.push(Field {
+            name: field.name,
+            info,
+            access: field.access.unwrap_or(raw.access),
+            values,
+        });
+    }
+
+    if let Some(unknown) = raw
+        .index
+        .iter()
+        .find(|index| !fields.iter().any(|f| &f.name == *index))
+    {
+        return Err(invalid(format!("{}: index {} is no field", name, unknown)));
+    }
+
+    Ok(RegisterDesc {
+        name,
+        aliases: raw.aliases,
+        device: raw.device,
+        offset: raw.offset,
+        access: raw.access,
+        feature: raw.feature,
+        index: raw.index,
+        fields,
+    })
+}
+
+fn normalize(name: &str) -> String {
+    name.to_ascii_lowercase().replace('-', "_")
+}
+
+impl RegisterDb {
+    /// Description of the 88Q5151, 88Q5152 and 88Q5192 built into the binary
+    pub fn builtin() -> Self {
+        Self::parse(BUILTIN).expect("builtin register description")
+    }
+
+    pub fn parse(text: &str) -> io::Result<Self> {
+        let raw: RawDb = toml::from_str(text).map_err(|e| invalid(e.to_string()))?;
+
+        let mut db = Self {
+            chips: raw.chips,
+            registers: Vec::new(),
+        };
+        for reg in raw.register {
+            let reg = convert(reg)?;
+            if db.register(reg.device, &reg.name).is_some() {
+                return Err(invalid(format!("register {} given twice", reg.name)));
+            }
+            db.registers.push(reg);
+        }
+        Ok(db)
+    }
+
+    /// Take the registers of `other`, replacing the ones of the same name
+    pub fn merge(&mut self, other: RegisterDb) {
+        for reg in other.registers {
+            match self
+                .registers
+                .iter_mut()
+                .find(|r| r.device == reg.device && r.name == reg.name)
+            {
+                Some(known) => *known = reg,
+                None => self.registers.push(reg),
+            }
+        }
+        for chip in other.chips {
+            if !self.chips.contains(&chip) {
+                self.chips.push(chip);
+            }
+        }
+    }
+
+    /// Registers of `device` in offset order
+    pub fn registers(&self, device: Device) -> Vec<&RegisterDesc> {
+        let mut regs: Vec<&RegisterDesc> = self
+            .registers
+            .iter()
+            .filter(|r| r.device == device)
+            .collect();
+        regs.sort_by_key(|r| r.offset);
+        regs
+    }
+
+    /// Register by name, `-` and `_` alike
+    pub fn register(&self, device: Device, name: &str) -> Option<&RegisterDesc> {
+        let name = normalize(name);
+        self.registers
+            .iter()
+            .find(|r| r.device == device && (r.name == name || r.aliases.contains(&name)))
+    }
+
+    /// Register at `offset` of `device`
+    pub fn register_at(&self, device: Device, offset: u8) -> Option<&RegisterDesc> {
+        self.registers
+            .iter()
+            .find(|r| r.device == device && r.offset == offset)
+    }
+
+    /// As `register`, failing with the names known for `device`
+    pub fn find(&self, device: Device, name: &str) -> Result<&RegisterDesc, String> {
+        self.register(device, name).ok_or_else(|| {
+            let known: Vec<&str> = self
+                .registers(device)
+                .iter()
+                .map(|r| r.name.as_str())
+                .collect();
+            format!(
+                "no {} register {}, one of: {}",
+                device,
+                name,
+                known.join(",")
+            )
+        })
+    }
+}
@@ -0,0 +1,160 @@
+mod sim_device;
+mod sim_transport;
+
+pub use sim_device::{AtuEntry, SimDevice, VtuEntry};
+pub use sim_device::{GLOBAL1_ADDR, GLOBAL2_ADDR};
+pub use sim_transport::SimTransport;
+
+use crate::message::customer_info_read::CustomerInfoReadResponse;
+use crate::message::fw_version::FwVersionResponse;
+use crate::message::getid::GetIdResponse;
+use crate::message::header::{RequestHeader, ResponseHeader};
+use crate::message::register::{RegisterRequest, RegisterResponse};
+use crate::message::response_error::{ResponseError, ResponseErrorExt};
+use crate::message::version_read::VersionReadResponse;
+use crate::message::{self, MessageHeaderOperation, MessageOperation, RMU_MULTICAST_ADDR};
+use crate::message_builder::MessageBuilder;
+use crate::message_code::MessageCode;
+use crate::packet_sock::ETH_P_RMU;
+
+/// ErrorResponseEx code for a RwRegister op list that does not parse
+pub const ERROR_CODE_BAD_REGOP: u16 = 0x0001;
+
+/// ErrorResponseEx code for a RwRegister op list longer than the device takes
+pub const ERROR_CODE_TOO_MANY_REGOPS: u16 = 0x0002;
+
+/// A set of simulated switches sharing one link
+///
+/// Frames go in the way they come off the wire, answers come back ready to send.
+#[derive(Debug, Default)]
+pub struct Simulator {
+    devices: Vec<SimDevice>,
+}
+
+impl Simulator {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn add_device(&mut self, dev: SimDevice) -> &mut Self {
+        self.devices.push(dev);
+        self
+    }
+
+    pub fn devices(&self) -> &[SimDevice] {
+        &self.devices
+    }
+
+    pub fn device_mut(&mut self, devid: u8) -> Option<&mut SimDevice> {
+        self.devices.iter_mut().find(|dev| dev.devid() == devid)
+    }
+
+    /// Answer one request frame, anything that is not a RMU request is ignored
+    pub fn handle_frame(&mut self, frame: &[u8]) -> anyhow::Result<Vec<Vec<u8>>> {
+        // ethernet + dsa + rmu header, from_cpu tag
+        if frame.len() < 28
+            || u16::from_be_bytes([frame[12], frame[13]]) != ETH_P_RMU as u16
+            || frame[16] & 0xC0 != 0x40
+        {
+            return Ok(Vec::new());
+        }
+
+        let reqhdr = RequestHeader::unmarshal(frame)?;
+        let mut responses = Vec::new();
+
+        for dev in self.devices.iter_mut() {
+            if dev.devid() != reqhdr.device_id() {
+                continue;
+            }
+
+            let dmac = reqhdr.destination_address();
+            if dmac != RMU_MULTICAST_ADDR && dmac != dev.mac() {
+                continue;
+            }
+
+            let header = MessageBuilder::<ResponseHeader>::new()
+                .destination_address(&reqhdr.source_address())
+                .source_address(&dev.mac())
+                .device_id(dev.devid())
+                .set_sequence_number(reqhdr.sequence_number())
+                .product_number(dev.product_number())
+                .format(reqhdr.format());
+
+            let code = MessageCode::try_from(reqhdr.code())
+                .ok()
+                .filter(|_| dev.supports(reqhdr.code()));
+
+            let frame = match code {
+                Some(MessageCode::GetId) => {
+                    let mut resp = Into::<MessageBuilder<GetIdResponse>>::into(header).build()?;
+                    marshal_frame(&mut resp)?
+                }
+                Some(MessageCode::VersionRead) => {
+                    let mut resp = Into::<MessageBuilder<VersionReadResponse>>::into(header)
+                        .crc32(dev.crc32())
+                        .build()?;
+                    marshal_frame(&mut resp)?
+                }
+                Some(MessageCode::CustomerInfoRead) => {
+                    let mut resp = Into::<MessageBuilder<CustomerInfoReadResponse>>::into(header)
+                        .info(dev.customer_info())
+                        .build()?;
+                    marshal_frame(&mut resp)?
+                }
+                Some(MessageCode::FwVersionGet) => {
+                    let (api, variant, release) = dev.fw_version();
+                    let mut resp = Into::<MessageBuilder<FwVersionResponse>>::into(header)
+                        .api_number(api)
+                        .variant_number(variant)
+                        .release_number(release)
+                        .build_string(dev.build_string())
+                        .build()?;
+                    marshal_frame(&mut resp)?
+                }
+                Some(MessageCode::RwRegister) => match RegisterRequest::unmarshal(frame) {
+                    Ok(req) if req.regops.len() > dev.max_regops() => {
+                        let mut resp = Into::<MessageBuilder<ResponseErrorExt>>::into(header)
+                            .request_format(reqhdr.format())
+                            .request_code(reqhdr.code())
+                            .error_code(ERROR_CODE_TOO_MANY_REGOPS)
+                            .build()?;
+                        marshal_frame(&mut resp)?
+                    }
+                    Ok(req) => {
+                        let regops = dev.run_regops(req.regops.as_ref());
+                        let mut resp = Into::<MessageBuilder<RegisterResponse>>::into(header)
+                            .regops(regops)
+                            .build()?;
+                        marshal_frame(&mut resp)?
+                    }
+                    Err(_) => {
+                        let mut resp = Into::<MessageBuilder<ResponseErrorExt>>::into(header)
+                            .request_format(reqhdr.format())
+                            .request_code(reqhdr.code())
+                            .error_code(ERROR_CODE_BAD_REGOP)
+                            .build()?;
+                        marshal_frame(&mut resp)?
+                    }
+                },
+                Some(MessageCode::ErrorResponse | MessageCode::ErrorResponseEx) => continue,
+                None => {
+                    let mut resp = Into::<MessageBuilder<ResponseError>>::into(header)
+                        .request_format(reqhdr.format())
+                        .request_code(reqhdr.code())
+                        .build()?;
+                    marshal_frame(&mut resp)?
+                }
+            };
+
+            responses.push(frame);
+        }
+
+        Ok(responses)
+    }
+}
+
+fn marshal_frame<T: MessageOperation>(msg: &mut T) -> anyhow::Result<Vec<u8>> {
+    let mut buf = message::prealloc_buffer(msg);
+    let _ = msg.marshal(&mut buf[..])?;
+    Ok(buf)
+}
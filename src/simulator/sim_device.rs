@@ -0,0 +1,482 @@
+use std::collections::{BTreeMap, HashMap};
+
+use bit_ops::bitops_u16;
+
+use crate::chip::{Chip, DEFAULT_PORT_COUNT};
+use crate::message::register::{
+    RegOpRequest, RegOpResponse, RegOpResponseList, MAX_REGOPS_PER_FRAME,
+};
+use crate::reginfo::{register_db, u16_set_bits, Access, Device, RegisterDesc};
+use crate::reginfo::{
+    u16_get_bits, AtuData, AtuOperation, AtuOperationAtuOpValue, Global1Register,
+    PortStatusCModeValue, VtuOperationVtuOpValue,
+};
+pub use crate::reginfo::{GLOBAL1_ADDR, GLOBAL2_ADDR};
+
+// Global1 registers driving the ATU/VTU engines
+const G1_VTU_FID: u8 = Global1Register::VtuFid as u8;
+const G1_VTU_SID: u8 = Global1Register::VtuSid as u8;
+const G1_VTU_OP: u8 = Global1Register::VtuOperation as u8;
+const G1_VTU_VID: u8 = Global1Register::VtuVid as u8;
+const G1_VTU_DATA_P0P7: u8 = Global1Register::VtuDataP0p7 as u8;
+const G1_VTU_DATA_P8P9: u8 = Global1Register::VtuDataP8p9 as u8;
+const G1_ATU_FID: u8 = Global1Register::AtuFid as u8;
+const G1_ATU_OP: u8 = Global1Register::AtuOperation as u8;
+const G1_ATU_DATA: u8 = Global1Register::AtuData as u8;
+const G1_ATU_MAC01: u8 = Global1Register::AtuMac01 as u8;
+const G1_ATU_MAC23: u8 = Global1Register::AtuMac23 as u8;
+const G1_ATU_MAC45: u8 = Global1Register::AtuMac45 as u8;
+
+const OP_BUSY: u8 = 15;
+
+const BROADCAST: [u8; 6] = [0xFF; 6];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AtuEntry {
+    pub entry_state: u8,
+    pub portvec: u16,
+    pub qpri: u8,
+    pub fpri: u8,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VtuEntry {
+    pub fid: u16,
+    pub sid: u8,
+    pub page: bool,
+    /// 2-bit member tag per port, port0 at bit 0
+    pub member_tags: u32,
+}
+
+/// One simulated 88Q5152 behind a devid and mac address
+#[derive(Debug)]
+pub struct SimDevice {
+    mac: [u8; 6],
+    devid: u8,
+    product_number: u16,
+    crc32: u32,
+    customer_info: String,
+    api_number: u16,
+    variant_number: u16,
+    release_number: u16,
+    build_string: String,
+    unsupported: Vec<u16>,
+    max_regops: usize,
+    regs: HashMap<u8, [u16; 32]>,
+    atu: BTreeMap<(u16, [u8; 6]), AtuEntry>,
+    vtu: BTreeMap<u16, VtuEntry>,
+    /// Entries of the Global2 indirect tables by register and index bits
+    tables: HashMap<(u8, u16), u16>,
+}
+
+impl SimDevice {
+    pub fn new(devid: u8, mac: [u8; 6], product_number: u16) -> Self {
+        let mut dev = Self {
+            mac,
+            devid,
+            product_number,
+            crc32: 0,
+            customer_info: String::from("mrmu simulator"),
+            api_number: 0,
+            variant_number: 0,
+            release_number: 0,
+            build_string: String::from("mrmu-sim"),
+            unsupported: Vec::new(),
+            max_regops: MAX_REGOPS_PER_FRAME,
+            regs: HashMap::new(),
+            atu: BTreeMap::new(),
+            vtu: BTreeMap::new(),
+            tables: HashMap::new(),
+        };
+
+        let port_count = Chip::from_product_number(product_number)
+            .map(|chip| chip.port_count())
+            .unwrap_or(DEFAULT_PORT_COUNT);
+        for port in 0..port_count {
+            // link up, full duplex, 1000M, rgmii on port 0, sgmii and the cpu
+            // on the last two, t1 in between
+            let c_mode = match port {
+                0 => PortStatusCModeValue::Rgmii,
+                p if p == port_count - 2 => PortStatusCModeValue::Sgmii,
+                p if p == port_count - 1 => PortStatusCModeValue::Cpu,
+                _ => PortStatusCModeValue::T1Phy,
+            };
+            dev.set_register(port, 0x00, 0x0E00 | c_mode as u16);
+            dev.set_register(port, 0x03, product_number);
+            // forwarding, flood all
+            dev.set_register(port, 0x04, 0x000F);
+            // member of all other ports
+            dev.set_register(port, 0x06, 0x03FF & !(1 << port));
+            dev.set_register(port, 0x07, 0x0001);
+        }
+        dev.set_register(GLOBAL1_ADDR, G1_ATU_MAC01, 0xFFFF);
+        dev.set_register(GLOBAL1_ADDR, G1_ATU_MAC23, 0xFFFF);
+        dev.set_register(GLOBAL1_ADDR, G1_ATU_MAC45, 0xFFFF);
+        dev.set_register(GLOBAL1_ADDR, G1_VTU_VID, 0x0FFF);
+
+        dev
+    }
+
+    pub fn mac(&self) -> [u8; 6] {
+        self.mac
+    }
+
+    pub fn devid(&self) -> u8 {
+        self.devid
+    }
+
+    pub fn product_number(&self) -> u16 {
+        self.product_number
+    }
+
+    pub fn crc32(&self) -> u32 {
+        self.crc32
+    }
+    pub fn set_crc32(&mut self, val: u32) -> &mut Self {
+        self.crc32 = val;
+        self
+    }
+
+    pub fn customer_info(&self) -> &str {
+        &self.customer_info
+    }
+    pub fn set_customer_info(&mut self, val: &str) -> &mut Self {
+        self.customer_info = val.to_string();
+        self
+    }
+
+    pub fn fw_version(&self) -> (u16, u16, u16) {
+        (self.api_number, self.variant_number, self.release_number)
+    }
+    pub fn set_fw_version(&mut self, api: u16, variant: u16, release: u16) -> &mut Self {
+        self.api_number = api;
+        self.variant_number = variant;
+        self.release_number = release;
+        self
+    }
+
+    pub fn build_string(&self) -> &str {
+        &self.build_string
+    }
+    pub fn set_build_string(&mut self, val: &str) -> &mut Self {
+        self.build_string = val.to_string();
+        self
+    }
+
+    /// Whether requests with this code get an answer other than ErrorResponse
+    pub fn supports(&self, code: u16) -> bool {
+        !self.unsupported.contains(&code)
+    }
+    /// Reject a request code like firmware that does not implement it
+    pub fn set_unsupported(&mut self, code: u16) -> &mut Self {
+        self.unsupported.push(code);
+        self
+    }
+
+    /// Longest RwRegister op list taken, longer ones get an error response
+    pub fn max_regops(&self) -> usize {
+        self.max_regops
+    }
+    pub fn set_max_regops(&mut self, max: usize) -> &mut Self {
+        self.max_regops = max;
+        self
+    }
+
+    pub fn register(&self, addr: u8, reg: u8) -> u16 {
+        self.regs
+            .get(&addr)
+            .map(|file| file[(reg & 0x1F) as usize])
+            .unwrap_or(0)
+    }
+
+    /// Change a register without side effects, unlike a write through RMU
+    pub fn set_register(&mut self, addr: u8, reg: u8, val: u16) -> &mut Self {
+        self.regs.entry(addr).or_insert([0; 32])[(reg & 0x1F) as usize] = val;
+        self
+    }
+
+    pub fn add_atu_entry(&mut self, fid: u16, mac: [u8; 6], entry: AtuEntry) -> &mut Self {
+        self.atu.insert((fid, mac), entry);
+        self
+    }
+
+    pub fn atu_entries(&self) -> &BTreeMap<(u16, [u8; 6]), AtuEntry> {
+        &self.atu
+    }
+
+    pub fn add_vtu_entry(&mut self, vid: u16, entry: VtuEntry) -> &mut Self {
+        self.vtu.insert(vid, entry);
+        self
+    }
+
+    pub fn vtu_entries(&self) -> &BTreeMap<u16, VtuEntry> {
+        &self.vtu
+    }
+
+    /// Run a RwRegister op list, stop after the first WaitOnBit that never settles
+    pub fn run_regops(&mut self, regops: &[RegOpRequest]) -> RegOpResponseList {
+        let mut resplist = RegOpResponseList::new();
+
+        for regop in regops {
+            match *regop {
+                RegOpRequest::Read { addr, reg } => {
+                    let data = self.register(addr, reg);
+                    resplist.add_regop(RegOpResponse::Read { addr, reg, data });
+                }
+                RegOpRequest::Write { addr, reg, data } => {
+                    self.write_register(addr, reg, data);
+                    resplist.add_regop(RegOpResponse::Write { addr, reg, data });
+                }
+                RegOpRequest::WaitOnBit0 { addr, reg, bit } => {
+                    let settled =
+                        bitops_u16::get_bit(self.register(addr, reg), (bit & 0x0F).into()) == 0;
+                    let result = if settled { 0x00 } else { 0xFF };
+                    resplist.add_regop(RegOpResponse::WaitOnBit0 {
+                        addr,
+                        reg,
+                        bit,
+                        result,
+                    });
+                    if !settled {
+                        break;
+                    }
+                }
+                RegOpRequest::WaitOnBit1 { addr, reg, bit } => {
+                    let settled =
+                        bitops_u16::get_bit(self.register(addr, reg), (bit & 0x0F).into()) == 1;
+                    let result = if settled { 0x00 } else { 0xFF };
+                    resplist.add_regop(RegOpResponse::WaitOnBit1 {
+                        addr,
+                        reg,
+                        bit,
+                        result,
+                    });
+                    if !settled {
+                        break;
+                    }
+                }
+                RegOpRequest::EndOfList => break,
+            }
+        }
+
+        resplist
+    }
+
+    /// Store a written value, self clearing bits read back 0
+    fn write_register(&mut self, addr: u8, reg: u8, data: u16) {
+        let sc_mask = register_db()
+            .register_at(Device::of_smi_addr(addr), reg)
+            .map_or(0, |desc| desc.mask_of(Access::Sc));
+        self.set_register(addr, reg, data & !sc_mask);
+
+        if addr == GLOBAL2_ADDR {
+            if let Some(desc) = register_db()
+                .register_at(Device::Global2, reg)
+                .filter(|desc| desc.is_indirect())
+            {
+                self.indirect_table(desc, data);
+                return;
+            }
+        }
+        if addr != GLOBAL1_ADDR || bitops_u16::get_bit(data, OP_BUSY.into()) == 0 {
+            return;
+        }
+
+        // engines finish immediately, busy is never seen set
+        match reg {
+            G1_ATU_OP => self.atu_operation(data),
+            G1_VTU_OP => self.vtu_operation(data),
+            _ => (),
+        }
+    }
+
+    /// Global2 indirect table, an entry is stored with the update bit set and
+    /// the register reads back the entry at the index written last
+    fn indirect_table(&mut self, desc: &RegisterDesc, data: u16) {
+        let mut index_mask = 0;
+        let mut update_mask = 0;
+        for field in &desc.fields {
+            if desc.index.contains(&field.name) {
+                index_mask = u16_set_bits(index_mask, 0xFFFF, field.info);
+            } else if field.name == "update" {
+                update_mask = u16_set_bits(update_mask, 0xFFFF, field.info);
+            }
+        }
+
+        let key = (desc.offset, data & index_mask);
+        if data & update_mask != 0 {
+            self.tables.insert(key, data & !index_mask & !update_mask);
+        }
+        let entry = self.tables.get(&key).copied().unwrap_or(0);
+        self.set_register(GLOBAL2_ADDR, desc.offset, data & index_mask | entry);
+    }
+
+    fn atu_mac(&self) -> [u8; 6] {
+        let mut mac = [0u8; 6];
+        mac[0..2].copy_from_slice(&self.register(GLOBAL1_ADDR, G1_ATU_MAC01).to_be_bytes());
+        mac[2..4].copy_from_slice(&self.register(GLOBAL1_ADDR, G1_ATU_MAC23).to_be_bytes());
+        mac[4..6].copy_from_slice(&self.register(GLOBAL1_ADDR, G1_ATU_MAC45).to_be_bytes());
+        mac
+    }
+
+    fn set_atu_mac(&mut self, mac: &[u8; 6]) {
+        self.set_register(
+            GLOBAL1_ADDR,
+            G1_ATU_MAC01,
+            u16::from_be_bytes([mac[0], mac[1]]),
+        );
+        self.set_register(
+            GLOBAL1_ADDR,
+            G1_ATU_MAC23,
+            u16::from_be_bytes([mac[2], mac[3]]),
+        );
+        self.set_register(
+            GLOBAL1_ADDR,
+            G1_ATU_MAC45,
+            u16::from_be_bytes([mac[4], mac[5]]),
+        );
+    }
+
+    fn atu_operation(&mut self, op: u16) {
+        let atu_op = bitops_u16::get_bits(op, 3, 12);
+        let mut op = bitops_u16::clear_bit(op, OP_BUSY.into());
+
+        if atu_op == AtuOperationAtuOpValue::LoadPurge as u16 {
+            let fid = bitops_u16::get_bits(self.register(GLOBAL1_ADDR, G1_ATU_FID), 12, 0);
+            let mac = self.atu_mac();
+            let data = self.register(GLOBAL1_ADDR, G1_ATU_DATA);
+            let entry_state = u16_get_bits(data, AtuData::EntryState) as u8;
+
+            // entry state 0 purges
+            if entry_state == 0 {
+                self.atu.remove(&(fid, mac));
+            } else {
+                let entry = AtuEntry {
+                    entry_state,
+                    portvec: u16_get_bits(data, AtuData::PortVec),
+                    qpri: u16_get_bits(op, AtuOperation::MacQPri) as u8,
+                    fpri: u16_get_bits(op, AtuOperation::MacFPri) as u8,
+                };
+                self.atu.insert((fid, mac), entry);
+            }
+        } else if [
+            AtuOperationAtuOpValue::FlushMoveAll,
+            AtuOperationAtuOpValue::FlushMoveNonStatic,
+            AtuOperationAtuOpValue::FlushMoveAllFid,
+            AtuOperationAtuOpValue::FlushMoveNonStaticFid,
+        ]
+        .iter()
+        .any(|&code| code as u16 == atu_op)
+        {
+            let one_fid = atu_op == AtuOperationAtuOpValue::FlushMoveAllFid as u16
+                || atu_op == AtuOperationAtuOpValue::FlushMoveNonStaticFid as u16;
+            let non_static = atu_op == AtuOperationAtuOpValue::FlushMoveNonStatic as u16
+                || atu_op == AtuOperationAtuOpValue::FlushMoveNonStaticFid as u16;
+            let fid = bitops_u16::get_bits(self.register(GLOBAL1_ADDR, G1_ATU_FID), 12, 0);
+            self.atu_flush_move(one_fid.then_some(fid), non_static);
+        } else if atu_op == AtuOperationAtuOpValue::GetNext as u16 {
+            let fid = bitops_u16::get_bits(self.register(GLOBAL1_ADDR, G1_ATU_FID), 12, 0);
+            let cur = self.atu_mac();
+
+            // all ones restarts the walk from the lowest address
+            let next = self
+                .atu
+                .range((fid, [0; 6])..=(fid, BROADCAST))
+                .find(|((_, mac), _)| cur == BROADCAST || *mac > cur)
+                .map(|((_, mac), entry)| (*mac, *entry));
+
+            match next {
+                Some((mac, entry)) => {
+                    self.set_atu_mac(&mac);
+                    let data = bitops_u16::set_bits_n(
+                        0,
+                        &[(entry.entry_state.into(), 4, 0), (entry.portvec, 10, 4)],
+                    );
+                    self.set_register(GLOBAL1_ADDR, G1_ATU_DATA, data);
+                    op = bitops_u16::set_bits_exact_n(
+                        op,
+                        &[(entry.qpri.into(), 3, 8), (entry.fpri.into(), 3, 0)],
+                    );
+                }
+                None => {
+                    self.set_atu_mac(&BROADCAST);
+                    self.set_register(GLOBAL1_ADDR, G1_ATU_DATA, 0);
+                }
+            }
+        }
+
+        self.set_register(GLOBAL1_ADDR, G1_ATU_OP, op);
+    }
+
+    /// Flush or move the entries of `fid`, of all without, as AtuData selects
+    ///
+    /// Entry state 0 flushes every port, 0xF moves the port in the low nibble
+    /// of the port vector to the one in the next nibble, port 0xF flushes it.
+    fn atu_flush_move(&mut self, fid: Option<u16>, non_static: bool) {
+        let data = self.register(GLOBAL1_ADDR, G1_ATU_DATA);
+        let state = u16_get_bits(data, AtuData::EntryState);
+        let portvec = u16_get_bits(data, AtuData::PortVec);
+        let (from, to) = (portvec & 0x0F, (portvec >> 4) & 0x0F);
+
+        self.atu.retain(|(entry_fid, mac), entry| {
+            let is_static = mac[0] & 0x01 != 0 || entry.entry_state >= 0x8;
+            if fid.is_some_and(|fid| fid != *entry_fid) || (non_static && is_static) {
+                return true;
+            }
+            if state == 0 {
+                return false;
+            }
+            if entry.portvec & (1 << from) == 0 {
+                return true;
+            }
+            entry.portvec &= !(1 << from);
+            if to != 0x0F {
+                entry.portvec |= 1 << to;
+            }
+            entry.portvec != 0
+        });
+    }
+
+    fn vtu_operation(&mut self, op: u16) {
+        let vtu_op = bitops_u16::get_bits(op, 3, 12);
+
+        if vtu_op == VtuOperationVtuOpValue::GetNext as u16 {
+            let cur = bitops_u16::get_bits(self.register(GLOBAL1_ADDR, G1_VTU_VID), 12, 0);
+
+            // 0xFFF restarts the walk from the lowest vid
+            let next = self
+                .vtu
+                .iter()
+                .find(|(vid, _)| cur == 0xFFF || **vid > cur)
+                .map(|(vid, entry)| (*vid, *entry));
+
+            match next {
+                Some((vid, entry)) => {
+                    let vid_reg = bitops_u16::set_bits_n(
+                        0,
+                        &[(vid, 12, 0), (1, 1, 12), (entry.page.into(), 1, 13)],
+                    );
+                    self.set_register(GLOBAL1_ADDR, G1_VTU_VID, vid_reg);
+                    self.set_register(GLOBAL1_ADDR, G1_VTU_FID, entry.fid & 0x0FFF);
+                    self.set_register(GLOBAL1_ADDR, G1_VTU_SID, entry.sid.into());
+                    self.set_register(
+                        GLOBAL1_ADDR,
+                        G1_VTU_DATA_P0P7,
+                        (entry.member_tags & 0xFFFF) as u16,
+                    );
+                    self.set_register(
+                        GLOBAL1_ADDR,
+                        G1_VTU_DATA_P8P9,
+                        ((entry.member_tags >> 16) & 0x000F) as u16,
+                    );
+                }
+                None => {
+                    self.set_register(GLOBAL1_ADDR, G1_VTU_VID, 0x0FFF);
+                }
+            }
+        }
+
+        let op = bitops_u16::clear_bit(op, OP_BUSY.into());
+        self.set_register(GLOBAL1_ADDR, G1_VTU_OP, op);
+    }
+}
@@ -0,0 +1,61 @@
+use std::collections::VecDeque;
+use std::io::{self, ErrorKind};
+use std::sync::Mutex;
+use std::time::Instant;
+
+use crate::simulator::Simulator;
+use crate::transport::Transport;
+
+/// In-process link to a `Simulator`
+///
+/// Answers are queued as soon as a request is sent, so `recv` never waits for
+/// the deadline: an empty queue is reported as timeout right away.
+pub struct SimTransport {
+    smac: [u8; 6],
+    sim: Mutex<Simulator>,
+    pending: Mutex<VecDeque<Vec<u8>>>,
+}
+
+impl SimTransport {
+    pub fn new(smac: [u8; 6], sim: Simulator) -> Self {
+        Self {
+            smac,
+            sim: Mutex::new(sim),
+            pending: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Inspect or change the simulated devices between requests
+    pub fn with_simulator<R>(&self, f: impl FnOnce(&mut Simulator) -> R) -> R {
+        f(&mut self.sim.lock().unwrap())
+    }
+}
+
+impl Transport for SimTransport {
+    fn source_address(&self) -> [u8; 6] {
+        self.smac
+    }
+
+    async fn send(&self, frame: &[u8]) -> io::Result<usize> {
+        let responses = self
+            .sim
+            .lock()
+            .unwrap()
+            .handle_frame(frame)
+            .map_err(|e| io::Error::new(ErrorKind::InvalidData, e))?;
+
+        self.pending.lock().unwrap().extend(responses);
+        Ok(frame.len())
+    }
+
+    async fn recv(&self, buf: &mut [u8], _deadline: Instant) -> io::Result<usize> {
+        match self.pending.lock().unwrap().pop_front() {
+            Some(frame) => {
+                let len = frame.len().min(buf.len());
+                buf[..len].copy_from_slice(&frame[..len]);
+                Ok(len)
+            }
+            None => Err(ErrorKind::TimedOut.into()),
+        }
+    }
+}
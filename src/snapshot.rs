@@ -0,0 +1,226 @@
+//! Register values of a switch saved as JSON, and what differs between two
+use std::fmt;
+use std::io;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::reginfo::{u16_set_bits, Access, Device, Field, RegisterDb};
+
+/// Register values of one switch, as written by `dump`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub devid: u8,
+    pub product_number: u16,
+    pub registers: Vec<RegisterValue>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RegisterValue {
+    /// SMI device address, the port number for port registers
+    pub addr: u8,
+    pub reg: u8,
+    /// Register name when taken, for the reader only
+    pub name: String,
+    pub value: u16,
+}
+
+impl Snapshot {
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        serde_json::from_str(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut text = serde_json::to_string_pretty(self)?;
+        text.push('\n');
+        std::fs::write(path, text)
+    }
+
+    pub fn value(&self, addr: u8, reg: u8) -> Option<u16> {
+        self.registers
+            .iter()
+            .find(|r| r.addr == addr && r.reg == reg)
+            .map(|r| r.value)
+    }
+}
+
+/// A field, or a whole register where not decodable, differing between snapshots
+#[derive(Debug, Clone, PartialEq)]
+pub struct Change {
+    /// As taken by `regop`, e.g. `port3.port_control0.port_state`
+    pub path: String,
+    pub field: Option<Field>,
+    /// `None` where the snapshot lacks the register
+    pub old: Option<u16>,
+    pub new: Option<u16>,
+}
+
+impl Change {
+    fn format_value(&self, val: Option<u16>) -> String {
+        match (val, &self.field) {
+            (None, _) => "-".to_string(),
+            (Some(val), None) => format!("0x{:04X}", val),
+            (Some(val), Some(field)) => match field.value_name(val) {
+                Some(name) => format!("{} ({})", val, name),
+                None => val.to_string(),
+            },
+        }
+    }
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {} -> {}",
+            self.path,
+            self.format_value(self.old),
+            self.format_value(self.new)
+        )
+    }
+}
+
+/// Field level differences from `old` to `new`, in the register order of `old`
+///
+/// Registers `db` does not describe, or bits outside of the described fields,
+/// are compared as whole register. Indirect tables are skipped, see
+/// `RegisterDesc::is_indirect`.
+pub fn diff(old: &Snapshot, new: &Snapshot, db: &RegisterDb) -> Vec<Change> {
+    let mut keys: Vec<(u8, u8)> = old.registers.iter().map(|r| (r.addr, r.reg)).collect();
+    for reg in &new.registers {
+        if !keys.contains(&(reg.addr, reg.reg)) {
+            keys.push((reg.addr, reg.reg));
+        }
+    }
+
+    let mut changes = Vec::new();
+    for (addr, reg) in keys {
+        let (a, b) = (old.value(addr, reg), new.value(addr, reg));
+        if a == b {
+            continue;
+        }
+
+        let desc = db.register_at(Device::of_smi_addr(addr), reg);
+        if desc.is_some_and(|desc| desc.is_indirect()) {
+            continue;
+        }
+        let regpath = match desc {
+            Some(desc) => format!("{}.{}", Device::path_name(addr), desc.name),
+            None => format!("{}.0x{:02X}", Device::path_name(addr), reg),
+        };
+        let (Some(a), Some(b), Some(desc)) = (a, b, desc) else {
+            changes.push(Change {
+                path: regpath,
+                field: None,
+                old: a,
+                new: b,
+            });
+            continue;
+        };
+
+        let mut described = 0u16;
+        for field in &desc.fields {
+            described |= (((1u32 << field.info.len) - 1) << field.info.shift) as u16;
+            if field.get(a) != field.get(b) {
+                changes.push(Change {
+                    path: format!("{}.{}", regpath, field.name),
+                    field: Some(field.clone()),
+                    old: Some(field.get(a)),
+                    new: Some(field.get(b)),
+                });
+            }
+        }
+        if (a ^ b) & !described != 0 {
+            changes.push(Change {
+                path: regpath,
+                field: None,
+                old: Some(a),
+                new: Some(b),
+            });
+        }
+    }
+    changes
+}
+
+/// Register write bringing a device back to a snapshot
+#[derive(Debug, Clone, PartialEq)]
+pub struct RestoreWrite {
+    /// As taken by `regop`, e.g. `port3.port_control0`
+    pub path: String,
+    pub addr: u8,
+    pub reg: u8,
+    /// Value on the device now
+    pub old: u16,
+    pub value: u16,
+    /// Bits reading back as written, the read/write fields
+    pub verify_mask: u16,
+}
+
+impl fmt::Display for RestoreWrite {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "WRITE:{},data=0x{:04X} was 0x{:04X}",
+            self.path, self.value, self.old
+        )
+    }
+}
+
+/// Writes turning the device values `live` into `saved`, in the order to apply
+///
+/// Only read/write fields are taken from `saved`. Self clearing and write only
+/// ones are written 0, so that no operation starts, as their dumped value tells
+/// nothing. Read only, undescribed and indirect table registers are left alone.
+/// Port states come last, the ports forward only once everything else is back.
+pub fn restore_writes(saved: &Snapshot, live: &Snapshot, db: &RegisterDb) -> Vec<RestoreWrite> {
+    let mut writes = Vec::new();
+    let mut port_states = Vec::new();
+    for saved_reg in &saved.registers {
+        let (addr, reg) = (saved_reg.addr, saved_reg.reg);
+        let (Some(old), Some(desc)) = (
+            live.value(addr, reg),
+            db.register_at(Device::of_smi_addr(addr), reg),
+        ) else {
+            continue;
+        };
+        if desc.is_indirect() {
+            continue;
+        }
+
+        let mut value = old;
+        let mut changed = false;
+        for field in &desc.fields {
+            value = match field.access {
+                Access::Rw => {
+                    changed |= field.get(saved_reg.value) != field.get(old);
+                    u16_set_bits(value, field.get(saved_reg.value), field.info)
+                }
+                Access::Sc | Access::Wo => u16_set_bits(value, 0, field.info),
+                Access::Ro => value,
+            };
+        }
+        if !changed {
+            continue;
+        }
+
+        let write = RestoreWrite {
+            path: format!("{}.{}", Device::path_name(addr), desc.name),
+            addr,
+            reg,
+            old,
+            value,
+            verify_mask: desc.mask_of(Access::Rw),
+        };
+        if desc.device == Device::Port && desc.fields.iter().any(|f| f.name == "port_state") {
+            port_states.push(write);
+        } else {
+            writes.push(write);
+        }
+    }
+
+    // globals before the ports
+    writes.sort_by_key(|w| Device::of_smi_addr(w.addr) == Device::Port);
+    writes.extend(port_states);
+    writes
+}
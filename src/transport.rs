@@ -0,0 +1,18 @@
+use std::future::Future;
+use std::io;
+use std::time::Instant;
+
+/// Frame level access to a RMU capable link
+///
+/// Commands only see whole ethernet frames, so the AF_PACKET socket, a capture
+/// file or an in-process simulator can be plugged in behind the same interface.
+pub trait Transport {
+    /// Mac address used as source of outgoing requests
+    fn source_address(&self) -> [u8; 6];
+
+    /// Send one frame, return the number of bytes written
+    fn send(&self, frame: &[u8]) -> impl Future<Output = io::Result<usize>>;
+
+    /// Receive one frame into `buf`, fail with `ErrorKind::TimedOut` once `deadline` passed
+    fn recv(&self, buf: &mut [u8], deadline: Instant) -> impl Future<Output = io::Result<usize>>;
+}
@@ -0,0 +1,55 @@
+//! Chip profiles picked by product number
+
+use mrmu::chip::{Chip, Feature, PortKind, PROFILES};
+use mrmu::reginfo::PortStatusCModeValue;
+
+#[test]
+fn product_numbers_select_profiles() {
+    let chip = Chip::from_product_number(0x1521).unwrap();
+    assert_eq!(chip.model(), "88Q5152");
+    assert_eq!(chip.revision, 1);
+    assert_eq!(chip.to_string(), "88Q5152 rev 1");
+    assert_eq!(chip.port_count(), 10);
+    assert!(chip.has(Feature::Macsec));
+
+    assert_eq!(
+        Chip::from_product_number(0x1510).unwrap().model(),
+        "88Q5151"
+    );
+    assert_eq!(
+        Chip::from_product_number(0x1920).unwrap().model(),
+        "88Q5192"
+    );
+    assert_eq!(Chip::from_product_number(0x0950), None);
+
+    for profile in &PROFILES {
+        assert_eq!(profile.product_num >> 12, 0, "{}", profile.model);
+    }
+}
+
+#[test]
+fn checks_name_the_chip() {
+    let chip = Chip::from_product_number(0x1510).unwrap();
+    assert!(chip.check_port(9).is_ok());
+    assert_eq!(
+        chip.check_port(10).unwrap_err(),
+        "88Q5151 has ports 0-9, no port 10"
+    );
+    assert_eq!(
+        chip.check_feature(Feature::Preemption).unwrap_err(),
+        "88Q5151 has no preemption"
+    );
+}
+
+#[test]
+fn c_mode_gives_port_kind() {
+    assert_eq!(
+        PortKind::from_c_mode(PortStatusCModeValue::T1Phy as u16),
+        Some(PortKind::T1)
+    );
+    assert_eq!(PortKind::from_c_mode(0x7), Some(PortKind::Rgmii));
+    assert_eq!(PortKind::from_c_mode(0xA), Some(PortKind::Sgmii));
+    assert_eq!(PortKind::from_c_mode(0xE), Some(PortKind::Cpu));
+    assert_eq!(PortKind::from_c_mode(0x0), None);
+    assert_eq!(PortKind::Sgmii.to_string(), "sgmii");
+}
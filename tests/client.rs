@@ -0,0 +1,376 @@
+//! Use the mrmu library against the in-process simulator
+
+use std::io;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::{Duration, Instant};
+
+use mrmu::message::register::{
+    RegOpRequest, RegOpRequestList, RegOpResponse, RegisterResponse, MAX_REGOPS_PER_FRAME,
+};
+use mrmu::message::{MessageOperation, RMU_MULTICAST_ADDR};
+use mrmu::message_code::MessageCode;
+use mrmu::simulator::{SimDevice, SimTransport, Simulator, ERROR_CODE_TOO_MANY_REGOPS};
+use mrmu::{RmuClient, RmuError, Transport};
+
+const HOST_MAC: [u8; 6] = [0x02, 0x00, 0x00, 0x00, 0x00, 0x01];
+
+fn client(devices: Vec<SimDevice>) -> RmuClient<SimTransport> {
+    let mut sim = Simulator::new();
+    for dev in devices {
+        sim.add_device(dev);
+    }
+    RmuClient::new(SimTransport::new(HOST_MAC, sim))
+}
+
+/// Loses the first `lost` requests, answers every other one to a stale
+/// sequence number as well
+struct BadLink {
+    sim: SimTransport,
+    lost: AtomicU32,
+}
+
+impl Transport for BadLink {
+    fn source_address(&self) -> [u8; 6] {
+        self.sim.source_address()
+    }
+
+    async fn send(&self, frame: &[u8]) -> io::Result<usize> {
+        if self.lost.load(Ordering::Relaxed) > 0 {
+            self.lost.fetch_sub(1, Ordering::Relaxed);
+            return Ok(frame.len());
+        }
+
+        let mut stale = frame.to_vec();
+        stale[19] = stale[19].wrapping_sub(1);
+        self.sim.send(&stale).await?;
+        self.sim.send(frame).await
+    }
+
+    async fn recv(&self, buf: &mut [u8], deadline: Instant) -> io::Result<usize> {
+        self.sim.recv(buf, deadline).await
+    }
+}
+
+/// Puts a request of another process ahead of each one, under the same
+/// sequence number but reading the next register
+struct Crosstalk {
+    sim: SimTransport,
+}
+
+impl Transport for Crosstalk {
+    fn source_address(&self) -> [u8; 6] {
+        self.sim.source_address()
+    }
+
+    async fn send(&self, frame: &[u8]) -> io::Result<usize> {
+        let mut other = frame.to_vec();
+        other[29] += 1;
+        self.sim.send(&other).await?;
+        self.sim.send(frame).await
+    }
+
+    async fn recv(&self, buf: &mut [u8], deadline: Instant) -> io::Result<usize> {
+        self.sim.recv(buf, deadline).await
+    }
+}
+
+fn bad_link(lost: u32) -> RmuClient<BadLink> {
+    let mut sim = Simulator::new();
+    sim.add_device(SimDevice::new(0x00, sim_mac(0), 0x1520));
+    let mut client = RmuClient::new(BadLink {
+        sim: SimTransport::new(HOST_MAC, sim),
+        lost: AtomicU32::new(lost),
+    });
+    client.set_timeout(Duration::from_millis(10));
+    client
+}
+
+fn read_switch_id() -> RegOpRequestList {
+    let mut regops = RegOpRequestList::new();
+    regops.add_regop(RegOpRequest::Read {
+        addr: 0x00,
+        reg: 0x03,
+    });
+    regops
+}
+
+fn sim_mac(devid: u8) -> [u8; 6] {
+    [0x00, 0x50, 0x43, 0x00, 0x00, devid]
+}
+
+#[test]
+fn get_id_collects_every_device() {
+    let client = client(vec![
+        SimDevice::new(0x02, sim_mac(1), 0x1520),
+        SimDevice::new(0x02, sim_mac(2), 0x1510),
+        SimDevice::new(0x03, sim_mac(3), 0x1920),
+    ]);
+
+    let replies = smol::block_on(client.get_id(&RMU_MULTICAST_ADDR, 0x02)).unwrap();
+    let prodnos: Vec<u16> = replies
+        .iter()
+        .map(|reply| reply.as_ref().unwrap().header().product_number())
+        .collect();
+    assert_eq!(prodnos, [0x1520, 0x1510]);
+}
+
+#[test]
+fn register_ops_write_then_read() {
+    let client = client(vec![SimDevice::new(0x00, sim_mac(0), 0x1520)]);
+
+    let mut regops = RegOpRequestList::new();
+    regops.add_regop(RegOpRequest::Write {
+        addr: 0x03,
+        reg: 0x1A,
+        data: 0x3EEF,
+    });
+    regops.add_regop(RegOpRequest::Read {
+        addr: 0x03,
+        reg: 0x1A,
+    });
+
+    let replies = smol::block_on(client.register_ops(&sim_mac(0), 0x00, regops)).unwrap();
+    assert_eq!(replies.len(), 1);
+
+    let resp = replies[0].as_ref().unwrap();
+    match resp.regops.as_ref()[1] {
+        RegOpResponse::Read { data, .. } => assert_eq!(data, 0x3EEF),
+        ref op => panic!("unexpected {op:?}"),
+    }
+    assert_eq!(
+        client
+            .transport()
+            .with_simulator(|sim| sim.devices()[0].register(0x03, 0x1A)),
+        0x3EEF
+    );
+}
+
+#[test]
+fn global2_tables_keep_entries_behind_the_pointer() {
+    let client = client(vec![SimDevice::new(0x00, sim_mac(0), 0x1520)]);
+
+    // device_mapping: target device 3 to port 2, device 4 to port 7, then
+    // only move the pointer back to device 3
+    let mut regops = RegOpRequestList::new();
+    for data in [0x8302, 0x8407, 0x0300] {
+        regops.add_regop(RegOpRequest::Write {
+            addr: 0x1C,
+            reg: 0x06,
+            data,
+        });
+        regops.add_regop(RegOpRequest::Read {
+            addr: 0x1C,
+            reg: 0x06,
+        });
+    }
+
+    let resp = smol::block_on(client.register_ops_one(&sim_mac(0), 0x00, regops)).unwrap();
+    let reads: Vec<u16> = resp
+        .regops
+        .as_ref()
+        .iter()
+        .filter_map(|op| match *op {
+            RegOpResponse::Read { data, .. } => Some(data),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(reads, [0x0302, 0x0407, 0x0302]);
+}
+
+#[test]
+fn unsupported_request_is_device_error() {
+    let mut dev = SimDevice::new(0x00, sim_mac(0), 0x1520);
+    dev.set_unsupported(MessageCode::VersionRead as u16);
+    let client = client(vec![dev]);
+
+    let replies = smol::block_on(client.version_read(&RMU_MULTICAST_ADDR, 0x00)).unwrap();
+    let err = replies[0].as_ref().unwrap_err();
+    assert_eq!(err.mac, sim_mac(0));
+    assert_eq!(err.request_code, MessageCode::VersionRead as u16);
+    assert_eq!(err.error_code, None);
+}
+
+#[test]
+fn no_device_no_reply() {
+    let client = client(vec![SimDevice::new(0x00, sim_mac(0), 0x1520)]);
+
+    let replies = smol::block_on(client.customer_info_read(&RMU_MULTICAST_ADDR, 0x05)).unwrap();
+    assert!(replies.is_empty());
+}
+
+#[test]
+fn stale_sequence_number_is_dropped() {
+    let client = bad_link(0);
+
+    let replies = smol::block_on(client.get_id(&RMU_MULTICAST_ADDR, 0x00)).unwrap();
+    assert_eq!(replies.len(), 1);
+    let first = replies[0].as_ref().unwrap().header().sequence_number();
+
+    let resp = smol::block_on(client.register_ops_one(&sim_mac(0), 0x00, read_switch_id()));
+    let seqno = resp.unwrap().header().sequence_number();
+    assert_eq!(seqno, first.wrapping_add(1));
+}
+
+#[test]
+fn reply_to_other_ops_is_dropped() {
+    let mut sim = Simulator::new();
+    sim.add_device(SimDevice::new(0x00, sim_mac(0), 0x1520));
+    let mut client = RmuClient::new(Crosstalk {
+        sim: SimTransport::new(HOST_MAC, sim),
+    });
+    client.set_timeout(Duration::from_millis(10));
+
+    let resp = smol::block_on(client.register_ops_one(&sim_mac(0), 0x00, read_switch_id()));
+    match resp.unwrap().regops.as_ref()[..] {
+        [RegOpResponse::Read {
+            reg: 0x03, data, ..
+        }] => assert_eq!(data, 0x1520),
+        ref ops => panic!("unexpected {ops:?}"),
+    }
+}
+
+#[test]
+fn retry_after_lost_request() {
+    let mut client = bad_link(2);
+    client.set_retries(2);
+
+    let resp = smol::block_on(client.register_ops_one(&sim_mac(0), 0x00, read_switch_id()));
+    match resp.unwrap().regops.as_ref()[0] {
+        RegOpResponse::Read { data, .. } => assert_eq!(data, 0x1520),
+        ref op => panic!("unexpected {op:?}"),
+    }
+}
+
+#[test]
+fn timeout_without_reply() {
+    let client = bad_link(1);
+
+    let resp = smol::block_on(client.register_ops_one(&sim_mac(0), 0x00, read_switch_id()));
+    assert!(matches!(resp, Err(RmuError::Timeout)));
+}
+
+/// Reads of registers 0-4 of every port, one op per register
+fn read_port_blocks() -> RegOpRequestList {
+    let mut regops = RegOpRequestList::new();
+    for addr in 0..10 {
+        for reg in 0..5 {
+            regops.add_regop(RegOpRequest::Read { addr, reg });
+        }
+    }
+    regops
+}
+
+fn read_data(resp: &RegisterResponse) -> Vec<(u8, u8, u16)> {
+    resp.regops
+        .as_ref()
+        .iter()
+        .map(|op| match *op {
+            RegOpResponse::Read { addr, reg, data } => (addr, reg, data),
+            ref op => panic!("unexpected {op:?}"),
+        })
+        .collect()
+}
+
+#[test]
+fn long_op_list_is_split_and_joined() {
+    let mut dev = SimDevice::new(0x00, sim_mac(0), 0x1520);
+    dev.set_max_regops(16);
+    let mut client = client(vec![dev]);
+    client.set_max_regops(16);
+    let start = client.next_sequence_number().wrapping_add(1);
+
+    let resp = smol::block_on(client.register_ops_one(&sim_mac(0), 0x00, read_port_blocks()));
+    let resp = resp.unwrap();
+    assert_eq!(resp.header().sequence_number(), start);
+    let expect: Vec<(u8, u8, u16)> = client.transport().with_simulator(|sim| {
+        let dev = &sim.devices()[0];
+        (0..10)
+            .flat_map(|addr| (0..5).map(move |reg| (addr, reg)))
+            .map(|(addr, reg)| (addr, reg, dev.register(addr, reg)))
+            .collect()
+    });
+    assert_eq!(read_data(&resp), expect);
+    // 50 ops in frames of 16
+    assert_eq!(client.next_sequence_number(), start.wrapping_add(4));
+}
+
+#[test]
+fn long_op_list_from_every_device() {
+    let mut devs = vec![
+        SimDevice::new(0x00, sim_mac(1), 0x1520),
+        SimDevice::new(0x00, sim_mac(2), 0x1510),
+    ];
+    for dev in devs.iter_mut() {
+        dev.set_max_regops(16);
+    }
+    let mut client = client(devs);
+    client.set_max_regops(16);
+
+    let replies =
+        smol::block_on(client.register_ops(&RMU_MULTICAST_ADDR, 0x00, read_port_blocks())).unwrap();
+    assert_eq!(replies.len(), 2);
+    for (reply, prodno) in replies.iter().zip([0x1520, 0x1510]) {
+        let data = read_data(reply.as_ref().unwrap());
+        assert_eq!(data.len(), 50);
+        assert_eq!(data[48], (9, 3, prodno));
+    }
+}
+
+#[test]
+fn op_list_beyond_device_limit_is_refused() {
+    let mut dev = SimDevice::new(0x00, sim_mac(0), 0x1520);
+    dev.set_max_regops(16);
+    let client = client(vec![dev]);
+
+    let resp = smol::block_on(client.register_ops_one(&sim_mac(0), 0x00, read_port_blocks()));
+    match resp {
+        Err(RmuError::DeviceError(e)) => {
+            assert_eq!(e.error_code, Some(ERROR_CODE_TOO_MANY_REGOPS))
+        }
+        other => panic!("unexpected {other:?}"),
+    }
+
+    let mut regops = RegOpRequestList::new();
+    for _ in 0..=MAX_REGOPS_PER_FRAME {
+        regops.add_regop(RegOpRequest::Read {
+            addr: 0x00,
+            reg: 0x03,
+        });
+    }
+    let mut buf = vec![0; regops.wire_size()];
+    assert!(matches!(
+        regops.marshal(&mut buf),
+        Err(RmuError::TooManyRegOps { .. })
+    ));
+}
+
+#[test]
+fn no_frame_after_unsettled_wait() {
+    let mut client = client(vec![SimDevice::new(0x00, sim_mac(0), 0x1520)]);
+    client.set_max_regops(2);
+
+    let mut regops = RegOpRequestList::new();
+    regops.add_regop(RegOpRequest::Read {
+        addr: 0x03,
+        reg: 0x1A,
+    });
+    regops.add_regop(RegOpRequest::WaitOnBit1 {
+        addr: 0x03,
+        reg: 0x1A,
+        bit: 0,
+    });
+    regops.add_regop(RegOpRequest::Write {
+        addr: 0x03,
+        reg: 0x1A,
+        data: 0xBEEF,
+    });
+
+    let resp = smol::block_on(client.register_ops_one(&sim_mac(0), 0x00, regops)).unwrap();
+    assert_eq!(resp.regops.len(), 2);
+    assert_eq!(
+        client
+            .transport()
+            .with_simulator(|sim| sim.devices()[0].register(0x03, 0x1A)),
+        0
+    );
+}
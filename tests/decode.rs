@@ -0,0 +1,227 @@
+//! Read captures back and decode the RMU frames in them
+
+use std::process::Command;
+use std::time::{Duration, UNIX_EPOCH};
+
+use mrmu::capture::{self, Direction, PcapngWriter};
+use mrmu::decode::{decode_frame, Correlation, Correlator};
+use mrmu::message::header::RequestHeader;
+use mrmu::message::register::{RegOpRequest, RegisterRequest};
+use mrmu::message::{self, MessageOperation, RMU_MULTICAST_ADDR};
+use mrmu::message_builder::MessageBuilder;
+
+const MRMU: &str = env!("CARGO_BIN_EXE_mrmu");
+
+fn regop_request() -> Vec<u8> {
+    let hdr = MessageBuilder::<RequestHeader>::new()
+        .destination_address(&RMU_MULTICAST_ADDR)
+        .source_address(&[0x02, 0, 0, 0, 0, 0x01])
+        .device_id(0x03)
+        .sequence_number(0x2A);
+    let mut req = Into::<MessageBuilder<RegisterRequest>>::into(hdr)
+        .add_regop(RegOpRequest::Read {
+            addr: 0x1B,
+            reg: 0x0B,
+        })
+        .add_regop(RegOpRequest::Write {
+            addr: 0x03,
+            reg: 0x04,
+            data: 0x007F,
+        })
+        .add_regop(RegOpRequest::WaitOnBit0 {
+            addr: 0x1B,
+            reg: 0x0B,
+            bit: 15,
+        })
+        .build()
+        .unwrap();
+
+    let mut buf = message::prealloc_buffer(&req);
+    req.marshal(&mut buf).unwrap();
+    buf
+}
+
+#[test]
+fn decode_register_request() {
+    let text = decode_frame(&regop_request()).unwrap().unwrap();
+    assert_eq!(
+        text,
+        "request  02:00:00:00:00:01 > 01:50:43:00:00:03 devid:0x03 seq:0x2A code:0x2000(RwRegister)\n\
+         \x20 READ:addr=0x1B,reg=0x0B\n\
+         \x20 WRITE:addr=0x03,reg=0x04,data=0x007F\n\
+         \x20 WaitOnBit0:addr=0x1B,reg=0x0B,bit=15"
+    );
+
+    let mut other = regop_request();
+    other[12..14].copy_from_slice(&[0x08, 0x00]);
+    assert!(decode_frame(&other).unwrap().is_none());
+    assert!(decode_frame(&regop_request()[..20]).is_err());
+}
+
+#[test]
+fn pcapng_round_trip() {
+    let frame = regop_request();
+    let ts = UNIX_EPOCH + Duration::from_micros(1_700_000_000_123_456);
+
+    let mut writer = PcapngWriter::new(Vec::new()).unwrap();
+    let ifid = writer.add_interface("veth0").unwrap();
+    writer
+        .write_packet(ifid, ts, Direction::Outbound, &frame)
+        .unwrap();
+    writer
+        .write_packet(ifid, ts, Direction::Inbound, &frame[..40])
+        .unwrap();
+    let data = writer.into_inner();
+
+    assert!(capture::is_capture(&data));
+    let frames = capture::read_capture(&data).unwrap();
+    assert_eq!(frames.len(), 2);
+    assert_eq!(frames[0].data, frame);
+    assert_eq!(frames[0].direction, Some(Direction::Outbound));
+    assert_eq!(
+        frames[0].timestamp,
+        Some(Duration::from_micros(1_700_000_000_123_456))
+    );
+    assert_eq!(frames[1].data, frame[..40]);
+    assert_eq!(frames[1].direction, Some(Direction::Inbound));
+}
+
+#[test]
+fn pcapng_malformed_blocks() {
+    let frame = regop_request();
+    let mut writer = PcapngWriter::new(Vec::new()).unwrap();
+    let ifid = writer.add_interface("veth0").unwrap();
+    let head = writer.into_inner();
+
+    // simple packet block without room for its original length
+    let mut data = head.clone();
+    for word in [3u32, 12, 12] {
+        data.extend_from_slice(&word.to_le_bytes());
+    }
+    assert!(capture::read_capture(&data).is_err());
+
+    // enhanced packet block claiming more than the block holds
+    let mut writer = PcapngWriter::new(Vec::new()).unwrap();
+    writer.add_interface("veth0").unwrap();
+    writer
+        .write_packet(ifid, UNIX_EPOCH, Direction::Outbound, &frame)
+        .unwrap();
+    let mut data = writer.into_inner();
+    let caplen = head.len() + 20;
+    data[caplen..caplen + 4].copy_from_slice(&0xFFFFu32.to_le_bytes());
+    let frames = capture::read_capture(&data).unwrap();
+    assert_eq!(frames.len(), 1);
+    assert!(frames[0].data.starts_with(&frame));
+    assert!(frames[0].data.len() < data.len() - head.len());
+}
+
+#[test]
+fn classic_pcap_big_endian() {
+    let frame = regop_request();
+    let mut data = 0xA1B2C3D4u32.to_be_bytes().to_vec();
+    data.extend_from_slice(&[0, 2, 0, 4]);
+    for word in [0u32, 0, 65535, 1] {
+        data.extend_from_slice(&word.to_be_bytes());
+    }
+    for word in [10u32, 500, frame.len() as u32, frame.len() as u32] {
+        data.extend_from_slice(&word.to_be_bytes());
+    }
+    data.extend_from_slice(&frame);
+
+    let frames = capture::read_capture(&data).unwrap();
+    assert_eq!(frames.len(), 1);
+    assert_eq!(frames[0].data, frame);
+    assert_eq!(frames[0].timestamp, Some(Duration::from_micros(10_000_500)));
+    assert_eq!(frames[0].direction, None);
+}
+
+#[test]
+fn hexdump_formats() {
+    let frame = regop_request();
+    let hex: Vec<String> = frame.iter().map(|b| format!("{:02x}", b)).collect();
+
+    // tcpdump -xx, offsets restart a frame
+    let mut tcpdump = String::new();
+    for _ in 0..2 {
+        for (line, chunk) in hex.chunks(16).enumerate() {
+            let words: Vec<String> = chunk.chunks(2).map(|w| w.concat()).collect();
+            tcpdump += &format!("\t0x{:04x}:  {}\n", line * 16, words.join(" "));
+        }
+    }
+    let frames = capture::parse_hexdump(&tcpdump).unwrap();
+    assert_eq!(frames.len(), 2);
+    assert!(frames.iter().all(|f| f.data == frame));
+
+    // hexdump -C, ascii column dropped
+    let mut canonical = String::new();
+    for (line, chunk) in hex.chunks(16).enumerate() {
+        canonical += &format!("{:08x}  {}  |.PC.....|\n", line * 16, chunk.join(" "));
+    }
+    assert_eq!(capture::parse_hexdump(&canonical).unwrap()[0].data, frame);
+
+    // bare bytes, frames split by a blank line
+    let plain = format!("{}\n\n{}\n", hex.concat(), hex.join(" "));
+    let frames = capture::parse_hexdump(&plain).unwrap();
+    assert_eq!(frames.len(), 2);
+    assert_eq!(frames[1].data, frame);
+}
+
+#[test]
+fn decode_command_reads_hexdump() {
+    let path = std::env::temp_dir().join(format!("mrmu-decode-{}.txt", std::process::id()));
+    let hex: Vec<String> = regop_request()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    std::fs::write(&path, hex.join(" ")).unwrap();
+
+    let output = Command::new(MRMU)
+        .args(["decode", path.to_str().unwrap()])
+        .output()
+        .unwrap();
+    let _ = std::fs::remove_file(&path);
+
+    assert!(output.status.success());
+    let out = String::from_utf8_lossy(&output.stdout);
+    assert!(out.starts_with("#1 request  02:00:00:00:00:01"), "{out}");
+    assert!(out.contains("\n  WRITE:addr=0x03,reg=0x04,data=0x007F\n"));
+}
+
+fn response_to(request: &[u8], src: [u8; 6]) -> Vec<u8> {
+    let mut resp = request.to_vec();
+    resp[0..6].copy_from_slice(&request[6..12]);
+    resp[6..12].copy_from_slice(&src);
+    resp[16] &= 0x1F;
+    resp
+}
+
+#[test]
+fn correlator_pairs_responses() {
+    let ms = Duration::from_millis;
+    let req = regop_request();
+    let mut correlator = Correlator::new(ms(100));
+
+    assert_eq!(correlator.observe(1, &req, ms(0)), None);
+    // multicast request, two devices answer
+    let first = correlator.observe(2, &response_to(&req, [0, 0x50, 0x43, 0, 0, 1]), ms(3));
+    assert_eq!(
+        first,
+        Some(Correlation {
+            request: 1,
+            latency: ms(3)
+        })
+    );
+    let second = correlator.observe(3, &response_to(&req, [0, 0x50, 0x43, 0, 0, 2]), ms(5));
+    assert_eq!(second.map(|c| c.request), Some(1));
+
+    // another sequence number is not a reply
+    let mut stale = response_to(&req, [0, 0x50, 0x43, 0, 0, 1]);
+    stale[19] += 1;
+    assert_eq!(correlator.observe(4, &stale, ms(6)), None);
+
+    let mut lost = req.clone();
+    lost[19] += 1;
+    assert_eq!(correlator.observe(5, &lost, ms(10)), None);
+    assert_eq!(correlator.expire(ms(105)), Vec::<usize>::new());
+    assert_eq!(correlator.expire(ms(110)), [5]);
+}
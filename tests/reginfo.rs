@@ -0,0 +1,148 @@
+//! Register layouts in `reginfo`
+
+use mrmu::reginfo::{u16_get_bits, u16_set_bits, BitInfo, Field};
+use mrmu::reginfo::{
+    AtuData, AtuOperationAtuOpValue, Global1Register, Global2Register, PortRegister,
+};
+use mrmu::reginfo::{Device, RegisterDb, RegisterPath, GLOBAL1_ADDR, GLOBAL2_ADDR};
+
+fn assert_consistent(name: &str, fields: &[Field]) {
+    assert!(!fields.is_empty(), "{name} without fields");
+
+    let mut used = 0u32;
+    for field in fields {
+        let mask = ((1u32 << field.info.len) - 1) << field.info.shift;
+        assert!(mask <= 0xFFFF, "{name}.{} beyond bit 15", field.name);
+        assert_eq!(used & mask, 0, "{name}.{} overlaps", field.name);
+        used |= mask;
+    }
+}
+
+#[test]
+fn register_layouts_are_consistent() {
+    let db = RegisterDb::builtin();
+    for device in [Device::Port, Device::Global1, Device::Global2] {
+        for desc in db.registers(device) {
+            assert_consistent(&desc.name, &desc.fields);
+        }
+    }
+}
+
+#[test]
+fn generated_enums_follow_the_description() {
+    let db = RegisterDb::builtin();
+    assert_eq!(Global1Register::AtuOperation as u8, 0x0B);
+    assert_eq!(Global1Register::IeeePri as u8, 0x18);
+    assert_eq!(Global1Register::StatsCounter10 as u8, 0x1F);
+    assert_eq!(Global2Register::PriorityOverride as u8, 0x0F);
+    assert_eq!(Global2Register::EepromCommand as u8, 0x14);
+    assert_eq!(Global2Register::Misc as u8, 0x1D);
+    assert_eq!(PortRegister::PortControl0 as u8, 0x04);
+
+    let atu_data = db.register(Device::Global1, "atu_data").unwrap();
+    let state = atu_data.fields.iter().find(|f| f.name == "entry_state");
+    assert_eq!(
+        Some(BitInfo::from(AtuData::EntryState)),
+        state.map(|f| f.info)
+    );
+    assert_eq!(u16_get_bits(0x002F, AtuData::PortVec), 0x0002);
+
+    let op = db.register(Device::Global1, "atu_op").unwrap().fields[1].clone();
+    assert_eq!(
+        op.value_of("get_next"),
+        Some(AtuOperationAtuOpValue::GetNext as u16)
+    );
+}
+
+#[test]
+fn port_register_field_values() {
+    let db = RegisterDb::builtin();
+    let fields = &db
+        .register(Device::Port, "switch_identifier")
+        .unwrap()
+        .fields;
+    let decoded: Vec<(&str, u16)> = fields
+        .iter()
+        .map(|field| (field.name.as_str(), field.get(0x1521)))
+        .collect();
+    assert_eq!(decoded, [("product_num", 0x152), ("rev", 0x1)]);
+
+    let fields = &db
+        .register(Device::Port, "default_vlan_id_priority")
+        .unwrap()
+        .fields;
+    let vid = fields.iter().find(|f| f.name == "default_vid").unwrap();
+    assert_eq!(vid.get(0xE123), 0x123);
+
+    // replaces the field instead of or-ing into it
+    assert_eq!(u16_set_bits(0xE123, 0x045, vid.info), 0xE045);
+}
+
+#[test]
+fn register_paths_resolve() {
+    let path: RegisterPath = "port3.port_status".parse().unwrap();
+    assert_eq!((path.addr, path.reg, path.field), (3, 0x00, None));
+
+    let path: RegisterPath = "global1.atu_op.atu_busy".parse().unwrap();
+    assert_eq!((path.addr, path.reg), (GLOBAL1_ADDR, 0x0B));
+    assert_eq!(path.field.unwrap().info.shift, 15);
+
+    let path: RegisterPath = "Global2.smi-phy-command".parse().unwrap();
+    assert_eq!((path.addr, path.reg), (GLOBAL2_ADDR, 0x18));
+
+    for bad in [
+        "port3",
+        "port27.port_status",
+        "port.port_status",
+        "global1.atu_fid.x.y",
+    ] {
+        assert!(bad.parse::<RegisterPath>().is_err(), "{bad}");
+    }
+}
+
+#[test]
+fn builtin_description_names_settings() {
+    let db = RegisterDb::builtin();
+    assert_eq!(db.chips, ["88Q5151", "88Q5152", "88Q5192"]);
+
+    let state = &db.register(Device::Port, "port-control0").unwrap().fields[9];
+    assert_eq!(state.value_name(3), Some("forwarding"));
+    assert_eq!(state.value_of("blocking"), Some(1));
+}
+
+#[test]
+fn description_files_are_checked_and_merged() {
+    let reg = |bits: &str| {
+        format!(
+            "[[register]]\nname = \"port_status\"\ndevice = \"port\"\noffset = 0\n\
+             fields = [ {{ name = \"link\", bits = \"{bits}\", access = \"ro\" }} ]\n"
+        )
+    };
+
+    let other = RegisterDb::parse(&reg("11")).unwrap();
+    let mut db = RegisterDb::builtin();
+    let count = db.registers(Device::Port).len();
+    db.merge(other);
+    assert_eq!(db.registers(Device::Port).len(), count);
+    let status = db.register(Device::Port, "port_status").unwrap();
+    assert_eq!(status.fields.len(), 1);
+    assert_eq!(status.fields[0].access.to_string(), "ro");
+
+    for bits in ["16", "3:4", "x"] {
+        assert!(RegisterDb::parse(&reg(bits)).is_err(), "{bits}");
+    }
+    assert!(RegisterDb::parse(&(reg("11") + &reg("11"))).is_err());
+    assert!(RegisterDb::parse(
+        "[[register]]\nname = \"x\"\ndevice = \"phy\"\noffset = 0\nfields = []\n"
+    )
+    .is_err());
+    assert!(RegisterDb::parse(
+        "[[register]]\nname = \"x\"\ndevice = \"port\"\noffset = 0\nindex = [\"ptr\"]\nfields = []\n"
+    )
+    .is_err());
+
+    let mapping = db.register(Device::Global2, "device_mapping").unwrap();
+    assert!(mapping.is_indirect());
+    assert_eq!(mapping.index, ["trg_dev_value"]);
+    assert!(!status.is_indirect());
+}
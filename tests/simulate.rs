@@ -0,0 +1,1020 @@
+//! Run the mrmu binary against `mrmu simulate` over a veth pair
+//!
+//! Creating the veth pair needs CAP_NET_ADMIN, the tests are skipped without it.
+
+use std::io::{BufRead, BufReader, Read};
+use std::process::{Child, Command, Output, Stdio};
+
+use mrmu::capture::{self, Direction};
+
+const MRMU: &str = env!("CARGO_BIN_EXE_mrmu");
+
+struct VethSim {
+    sim_end: String,
+    cli_end: String,
+    child: Child,
+}
+
+impl VethSim {
+    fn start(tag: &str, devices: &[&str]) -> Option<Self> {
+        let sim_end = format!("mrmu{}{}s", tag, std::process::id() % 10000);
+        let cli_end = format!("mrmu{}{}c", tag, std::process::id() % 10000);
+
+        let created = Command::new("ip")
+            .args([
+                "link", "add", &sim_end, "type", "veth", "peer", "name", &cli_end,
+            ])
+            .output()
+            .unwrap();
+        if !created.status.success() {
+            // only a missing CAP_NET_ADMIN skips, a clash on the name is a bug
+            let stderr = String::from_utf8_lossy(&created.stderr);
+            if stderr.contains("Operation not permitted") {
+                eprintln!("skip: cannot create veth pair, need CAP_NET_ADMIN");
+                return None;
+            }
+            panic!("cannot create veth pair {sim_end}: {stderr}");
+        }
+
+        for ifname in [&sim_end, &cli_end] {
+            let up = Command::new("ip")
+                .args(["link", "set", ifname, "up"])
+                .status()
+                .unwrap();
+            assert!(up.success());
+        }
+
+        let mut args = vec!["simulate", "--interface", &sim_end];
+        for device in devices {
+            args.push("--device");
+            args.push(device);
+        }
+
+        let mut child = Command::new(MRMU)
+            .args(&args)
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+
+        // one line per device once the socket is bound
+        let mut stdout = BufReader::new(child.stdout.take().unwrap());
+        for _ in devices {
+            let mut line = String::new();
+            stdout.read_line(&mut line).unwrap();
+            assert!(line.starts_with("simulate"), "unexpected: {line}");
+        }
+
+        Some(Self {
+            sim_end,
+            cli_end,
+            child,
+        })
+    }
+
+    fn mrmu(&self, args: &[&str]) -> Output {
+        let mut cmd = Command::new(MRMU);
+        cmd.arg(args[0]).args(["--interface", &self.cli_end]);
+        cmd.args(&args[1..]);
+        cmd.output().unwrap()
+    }
+}
+
+impl Drop for VethSim {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+        let _ = Command::new("ip")
+            .args(["link", "del", &self.sim_end])
+            .status();
+    }
+}
+
+fn stdout_of(output: &Output) -> String {
+    assert!(
+        output.status.success(),
+        "mrmu failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+#[test]
+fn scan_finds_all_devices() {
+    let Some(sim) = VethSim::start(
+        "sc",
+        &["devid=0", "devid=0x3,mac=02:11:22:33:44:55,prodno=0x1510"],
+    ) else {
+        return;
+    };
+
+    let out = stdout_of(&sim.mrmu(&["scan"]));
+    assert!(out.contains("Scan done: 2 devices"), "{out}");
+    assert!(out.contains("mac: 00:50:43:00:00:00 devid:0x00 prodno:0x1520 88Q5152 rev 0"));
+    assert!(out.contains("mac: 02:11:22:33:44:55 devid:0x03 prodno:0x1510 88Q5151 rev 0"));
+    assert!(
+        out.contains("  ports: 0:rgmii 1:t1 2:t1 3:t1 4:t1 5:t1 6:t1 7:t1 8:sgmii 9:cpu"),
+        "{out}"
+    );
+}
+
+#[test]
+fn regop_reads_switch_identifier() {
+    let Some(sim) = VethSim::start("rg", &["devid=0x3,prodno=0x1921"]) else {
+        return;
+    };
+
+    let out = stdout_of(&sim.mrmu(&[
+        "regop",
+        "--devid",
+        "3",
+        "--actions",
+        "READ:addr=0x2,reg=0x3",
+        "WRITE:reg=0x1A,data=0x55AA",
+        "READ:reg=0x1A",
+    ]));
+    assert!(out.contains("Devid:0x03"), "{out}");
+    assert!(out.contains("Read { addr: 0002, reg: 0003, data: 1921 }"));
+    assert!(out.contains("Read { addr: 0002, reg: 001A, data: 55AA }"));
+}
+
+#[test]
+fn regop_without_device_fails() {
+    let Some(sim) = VethSim::start("nd", &["devid=0"]) else {
+        return;
+    };
+
+    let output = sim.mrmu(&["regop", "--devid", "5", "--actions", "READ:addr=0,reg=0"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("No response"));
+}
+
+#[test]
+fn software_info_of_device() {
+    let Some(sim) = VethSim::start("sw", &["devid=0"]) else {
+        return;
+    };
+
+    let out = stdout_of(&sim.mrmu(&["software-info", "--devid", "0"]));
+    assert!(
+        out.contains("Mac:00:50:43:00:00:00 Id:0x00 Prodno:0x1520"),
+        "{out}"
+    );
+    assert!(out.contains(" info: mrmu simulator"));
+    assert!(out.contains(" build: mrmu-sim"));
+}
+
+#[test]
+fn read_port_decodes_status() {
+    let Some(sim) = VethSim::start("rp", &["devid=0"]) else {
+        return;
+    };
+
+    let out = stdout_of(&sim.mrmu(&[
+        "read-port",
+        "--devid",
+        "0",
+        "--portid",
+        "1",
+        "--register",
+        "port-status",
+        "--fields",
+        "link,speed",
+    ]));
+    assert_eq!(out, "port_status:\n link 1\n speed 2 (m1000)\n");
+}
+
+#[test]
+fn unsupported_request_reports_device_error() {
+    let Some(sim) = VethSim::start("ue", &["devid=0,unsupported=0xF293"]) else {
+        return;
+    };
+
+    let output = sim.mrmu(&["fw-version-get", "--devid", "0"]);
+    let out = stdout_of(&output);
+    assert!(out.is_empty(), "{out}");
+    assert!(String::from_utf8_lossy(&output.stderr).contains(
+        "Error: mac:00:50:43:00:00:00 devid:0x00 rejected request code:0xF293(FwVersionGet)"
+    ));
+
+    // the customer info still comes through
+    let out = stdout_of(&sim.mrmu(&["software-info", "--devid", "0"]));
+    assert!(out.contains(" info: mrmu simulator"), "{out}");
+    assert!(!out.contains(" build:"));
+}
+
+#[test]
+fn capture_records_both_directions() {
+    let Some(sim) = VethSim::start("cp", &["devid=0"]) else {
+        return;
+    };
+
+    let path = std::env::temp_dir().join(format!("mrmu-{}.pcapng", std::process::id()));
+    stdout_of(&sim.mrmu(&[
+        "regop",
+        "--devid",
+        "0",
+        "--actions",
+        "READ:addr=0,reg=3",
+        "--capture",
+        path.to_str().unwrap(),
+    ]));
+    let pcapng = std::fs::read(&path).unwrap();
+    let _ = std::fs::remove_file(&path);
+
+    // walk the blocks: section header, interface, then request and response
+    let u32_at = |off: usize| u32::from_le_bytes(pcapng[off..off + 4].try_into().unwrap());
+    let mut blocks = Vec::new();
+    let mut off = 0;
+    while off < pcapng.len() {
+        let (btype, len) = (u32_at(off), u32_at(off + 4) as usize);
+        assert_eq!(u32_at(off + len - 4) as usize, len);
+        blocks.push((btype, off));
+        off += len;
+    }
+    assert_eq!(off, pcapng.len());
+
+    let types: Vec<u32> = blocks.iter().map(|b| b.0).collect();
+    assert_eq!(types, [0x0A0D0D0A, 1, 6, 6]);
+    assert_eq!(u32_at(8), 0x1A2B3C4D);
+
+    let flags: Vec<u32> = blocks[2..]
+        .iter()
+        .map(|&(_, off)| {
+            let caplen = u32_at(off + 20) as usize;
+            let opt = off + 28 + caplen.next_multiple_of(4);
+            assert_eq!(u32_at(opt), 0x0004_0002, "epb_flags option");
+            // the ethertype of the recorded frame
+            assert_eq!(pcapng[off + 28 + 12..off + 28 + 14], [0x91, 0x01]);
+            u32_at(opt + 4)
+        })
+        .collect();
+    assert_eq!(flags, [0b10, 0b01]);
+}
+
+#[test]
+fn monitor_pairs_request_and_response() {
+    let Some(sim) = VethSim::start("mo", &["devid=0"]) else {
+        return;
+    };
+
+    let mut monitor = Command::new(MRMU)
+        .args(["monitor", "--interface", &sim.cli_end])
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut stdout = BufReader::new(monitor.stdout.take().unwrap());
+    let mut line = String::new();
+    stdout.read_line(&mut line).unwrap();
+    assert!(line.starts_with("monitor"), "unexpected: {line}");
+
+    stdout_of(&sim.mrmu(&["regop", "--devid", "0", "--actions", "READ:addr=0,reg=3"]));
+    std::thread::sleep(std::time::Duration::from_millis(200));
+    let _ = monitor.kill();
+    let _ = monitor.wait();
+
+    let mut out = String::new();
+    std::io::Read::read_to_string(&mut stdout, &mut out).unwrap();
+    let lines: Vec<&str> = out.lines().collect();
+    assert_eq!(lines.len(), 4, "{out}");
+    assert!(lines[0].starts_with("#1 ") && lines[0].contains(" out request "));
+    assert_eq!(lines[1], "  READ:addr=0x00,reg=0x03");
+    assert!(lines[2].contains(" in  response 00:50:43:00:00:00"));
+    assert!(lines[2].contains("[reply to #1 in "), "{out}");
+    assert_eq!(lines[3], "  READ:addr=0x00,reg=0x03 -> data=0x1520");
+}
+
+#[test]
+fn read_port_decodes_any_register() {
+    let Some(sim) = VethSim::start("ra", &["devid=0,prodno=0x1521"]) else {
+        return;
+    };
+
+    let out = stdout_of(&sim.mrmu(&[
+        "read-port",
+        "--devid",
+        "0",
+        "--portid",
+        "2",
+        "--register",
+        "switch-identifier",
+    ]));
+    assert_eq!(out, "switch_identifier:\n product_num 338\n rev 1\n");
+
+    let output = sim.mrmu(&[
+        "read-port",
+        "--devid",
+        "0",
+        "--portid",
+        "2",
+        "--register",
+        "port-control0",
+        "--fields",
+        "port_state,bogus",
+    ]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("no field bogus in port_control0"));
+}
+
+#[test]
+fn read_global1_by_name() {
+    let Some(sim) = VethSim::start("g1", &["devid=0"]) else {
+        return;
+    };
+
+    stdout_of(&sim.mrmu(&[
+        "regop",
+        "--devid",
+        "0",
+        "--actions",
+        "WRITE:addr=0x1B,reg=0x1C,data=0x0703",
+    ]));
+    let out = stdout_of(&sim.mrmu(&[
+        "read-global1",
+        "--devid",
+        "0",
+        "--register",
+        "switch-control2",
+        "--fields",
+        "rmu_mode,device_number",
+    ]));
+    assert_eq!(out, "switch_control2:\n rmu_mode 7\n device_number 3\n");
+}
+
+#[test]
+fn read_global2_indirect_table() {
+    let Some(sim) = VethSim::start("g2", &["devid=0"]) else {
+        return;
+    };
+
+    // update scratch byte 0x12, then move the pointer away
+    stdout_of(&sim.mrmu(&[
+        "regop",
+        "--devid",
+        "0",
+        "--actions",
+        "WRITE:addr=0x1C,reg=0x1A,data=0x92A5",
+        "WRITE:addr=0x1C,reg=0x1A,data=0x0300",
+    ]));
+    let out = stdout_of(&sim.mrmu(&[
+        "read-global2",
+        "--devid",
+        "0",
+        "--register",
+        "scratch-misc",
+        "--index",
+        "pointer=0x12",
+    ]));
+    assert_eq!(out, "scratch_misc:\n update 0\n pointer 18\n data 165\n");
+
+    // two index fields, priority 3 of the frame priority set
+    stdout_of(&sim.mrmu(&[
+        "regop",
+        "--devid",
+        "0",
+        "--actions",
+        "WRITE:addr=0x1C,reg=0x0F,data=0x9306",
+        "WRITE:addr=0x1C,reg=0x0F,data=0x8300",
+    ]));
+    let read_override = |fpri_set: &str| {
+        stdout_of(&sim.mrmu(&[
+            "read-global2",
+            "--devid",
+            "0",
+            "--register",
+            "priority-override",
+            "--index",
+            &format!("fpri_set={fpri_set},pointer=3"),
+            "--fields",
+            "data",
+        ]))
+    };
+    assert_eq!(read_override("1"), "priority_override:\n data 6\n");
+    assert_eq!(read_override("0"), "priority_override:\n data 0\n");
+
+    for (register, index) in [("smi-phy-data", "pointer=1"), ("trunk-mask", "pointer=1")] {
+        let out = sim.mrmu(&[
+            "read-global2",
+            "--devid",
+            "0",
+            "--register",
+            register,
+            "--index",
+            index,
+        ]);
+        assert!(!out.status.success());
+    }
+}
+
+#[test]
+fn write_port_changes_only_named_fields() {
+    let Some(sim) = VethSim::start("wp", &["devid=0"]) else {
+        return;
+    };
+
+    let out = stdout_of(&sim.mrmu(&[
+        "write-port",
+        "--devid",
+        "0",
+        "--portid",
+        "3",
+        "--register",
+        "port-control0",
+        "--set",
+        "port_state=learning,egress_floods=1,initial_pri=0x3",
+    ]));
+    assert!(out.starts_with("port_control0(H): 000F -> 0036\n"), "{out}");
+    assert!(
+        out.contains("\n egress_floods 1 (multicast)\n port_state 2 (learning)\n"),
+        "{out}"
+    );
+
+    let out = stdout_of(&sim.mrmu(&[
+        "read-port",
+        "--devid",
+        "0",
+        "--portid",
+        "3",
+        "--register",
+        "port-control0",
+        "--print-reg",
+        "--fields",
+        "port_state",
+    ]));
+    assert_eq!(
+        out,
+        "port_control0(H): 0036\nport_control0:\n port_state 2 (learning)\n"
+    );
+
+    for bad in [
+        "port_state=4",
+        "port_state=open",
+        "no_such_field=1",
+        "port_state",
+    ] {
+        let out = sim.mrmu(&[
+            "write-port",
+            "--devid",
+            "0",
+            "--portid",
+            "3",
+            "--register",
+            "port-control0",
+            "--set",
+            bad,
+        ]);
+        assert!(!out.status.success(), "{bad} accepted");
+    }
+}
+
+#[test]
+fn write_port_self_clearing_bit() {
+    let Some(sim) = VethSim::start("sc", &["devid=0"]) else {
+        return;
+    };
+
+    // the update bit reads back 0, the write still went through
+    let out = stdout_of(&sim.mrmu(&[
+        "write-port",
+        "--devid",
+        "0",
+        "--portid",
+        "2",
+        "--register",
+        "flow-control",
+        "--set",
+        "update=1,pointer=0x05,data=0x12",
+    ]));
+    assert!(out.starts_with("flow_control(H): 0000 -> 0512\n"), "{out}");
+}
+
+#[test]
+fn regop_symbolic_names() {
+    let Some(sim) = VethSim::start("sy", &["devid=0"]) else {
+        return;
+    };
+
+    let out = stdout_of(&sim.mrmu(&[
+        "regop",
+        "--devid",
+        "0",
+        "--actions",
+        "WRITE:global1.atu_fid,data=0x10",
+        "READ:global1.atu_fid",
+        "WaitBit0:global1.atu_op.atu_busy",
+        "READ:port3.switch_identifier",
+    ]));
+    assert!(
+        out.contains("Read { addr: 001B, reg: 0001, data: 0010 }"),
+        "{out}"
+    );
+    assert!(
+        out.contains("WaitOnBit0 { addr: 001B, reg: 000B, bit: 000F"),
+        "{out}"
+    );
+    assert!(
+        out.contains("Read { addr: 0003, reg: 0003, data: 1520 }"),
+        "{out}"
+    );
+
+    for bad in [
+        "READ:port3.no_such_register",
+        "READ:global3.atu_fid",
+        "WaitBit0:global1.atu_op.atu_op",
+        "WRITE:port3.port_control0.port_state,data=3",
+    ] {
+        let out = sim.mrmu(&["regop", "--devid", "0", "--actions", bad]);
+        assert!(!out.status.success(), "{bad} accepted");
+    }
+}
+
+#[test]
+fn regdb_file_adds_registers() {
+    let Some(sim) = VethSim::start("db", &["devid=0"]) else {
+        return;
+    };
+    let path = std::env::temp_dir().join(format!("mrmu-regdb-{}.toml", std::process::id()));
+    std::fs::write(
+        &path,
+        r#"
+[[register]]
+name = "scratch_word"
+device = "port"
+offset = 0x1A
+fields = [
+    { name = "hi", bits = "15:8" },
+    { name = "lo", bits = "7:0", values = { magic = 0xAA } },
+]
+"#,
+    )
+    .unwrap();
+
+    stdout_of(&sim.mrmu(&[
+        "regop",
+        "--devid",
+        "0",
+        "--actions",
+        "WRITE:addr=0x02,reg=0x1A,data=0x55AA",
+    ]));
+    let out = stdout_of(&sim.mrmu(&[
+        "read-port",
+        "--regdb",
+        path.to_str().unwrap(),
+        "--devid",
+        "0",
+        "--portid",
+        "2",
+        "--register",
+        "scratch-word",
+    ]));
+    let _ = std::fs::remove_file(&path);
+    assert_eq!(out, "scratch_word:\n hi 85\n lo 170 (magic)\n");
+}
+
+#[test]
+fn commands_check_the_detected_chip() {
+    let Some(sim) = VethSim::start("ch", &["devid=0,prodno=0x1510"]) else {
+        return;
+    };
+    let read_port = |port: &str, register: &str| {
+        sim.mrmu(&[
+            "read-port",
+            "--devid",
+            "0",
+            "--portid",
+            port,
+            "--register",
+            register,
+        ])
+    };
+
+    stdout_of(&read_port("9", "port_status"));
+
+    let out = read_port("10", "port_status");
+    assert!(!out.status.success());
+    let err = String::from_utf8_lossy(&out.stderr);
+    assert!(err.contains("88Q5151 has ports 0-9, no port 10"), "{err}");
+
+    let out = read_port("1", "preemption_control");
+    assert!(!out.status.success());
+    let err = String::from_utf8_lossy(&out.stderr);
+    assert!(err.contains("88Q5151 has no preemption"), "{err}");
+}
+
+#[test]
+fn max_regops_flag_splits_frames() {
+    let Some(sim) = VethSim::start("mr", &["devid=0,max_regops=8"]) else {
+        return;
+    };
+    let path = std::env::temp_dir().join(format!("mrmu-dump-mr-{}.json", std::process::id()));
+    let path = path.to_str().unwrap();
+
+    let out = sim.mrmu(&["dump", "--devid", "0", "--output", path]);
+    assert!(!out.status.success());
+    let out = stdout_of(&sim.mrmu(&[
+        "dump",
+        "--devid",
+        "0",
+        "--output",
+        path,
+        "--max-regops",
+        "8",
+    ]));
+    assert!(out.starts_with("246 registers"), "{out}");
+    let _ = std::fs::remove_file(path);
+}
+
+#[test]
+fn dump_and_diff_snapshots() {
+    let Some(sim) = VethSim::start("du", &["devid=0"]) else {
+        return;
+    };
+    let dir = std::env::temp_dir();
+    let before = dir.join(format!("mrmu-dump-a-{}.json", std::process::id()));
+    let after = dir.join(format!("mrmu-dump-b-{}.json", std::process::id()));
+    let dump = |path: &std::path::Path| {
+        stdout_of(&sim.mrmu(&["dump", "--devid", "0", "--output", path.to_str().unwrap()]))
+    };
+
+    let out = dump(&before);
+    assert!(out.starts_with("246 registers"), "{out}");
+    // indirect tables are not saved
+    let saved = std::fs::read_to_string(&before).unwrap();
+    assert!(!saved.contains("device_mapping"));
+    stdout_of(&sim.mrmu(&[
+        "regop",
+        "--devid",
+        "0",
+        "--actions",
+        "WRITE:port4.port_control0,data=0x000C",
+        "WRITE:global1.atu_fid,data=0x0010",
+    ]));
+    dump(&after);
+
+    let out = Command::new(MRMU)
+        .args(["diff", before.to_str().unwrap(), after.to_str().unwrap()])
+        .output()
+        .unwrap();
+    let _ = std::fs::remove_file(&before);
+    let _ = std::fs::remove_file(&after);
+    assert_eq!(
+        stdout_of(&out),
+        "port4.port_control0.port_state 3 (forwarding) -> 0 (disabled)\n\
+         global1.atu_fid.fid 0 -> 16\n"
+    );
+}
+
+#[test]
+fn restore_brings_back_a_dump() {
+    let Some(sim) = VethSim::start("rs", &["devid=0"]) else {
+        return;
+    };
+    let path = std::env::temp_dir().join(format!("mrmu-restore-{}.json", std::process::id()));
+    let snap = path.to_str().unwrap();
+    stdout_of(&sim.mrmu(&["dump", "--devid", "0", "--output", snap]));
+
+    stdout_of(&sim.mrmu(&[
+        "regop",
+        "--devid",
+        "0",
+        "--actions",
+        "WRITE:port5.port_control0,data=0x000C",
+        "WRITE:global2.switch_management,data=0x0080",
+        // a table entry, not in the dump and not restored
+        "WRITE:global2.device_mapping,data=0x8203",
+    ]));
+
+    let out = stdout_of(&sim.mrmu(&["restore", "--dry-run", snap]));
+    assert_eq!(
+        out,
+        "WRITE:global2.switch_management,data=0x0000 was 0x0080\n\
+         WRITE:port5.port_control0,data=0x000F was 0x000C\n\
+         2 registers to write, dry run\n"
+    );
+
+    let out = stdout_of(&sim.mrmu(&["restore", snap]));
+    assert!(out.ends_with("2 registers written\n"), "{out}");
+    let out = stdout_of(&sim.mrmu(&["restore", "--dry-run", snap]));
+    let _ = std::fs::remove_file(&path);
+    assert_eq!(out, "0 registers to write, dry run\n");
+}
+
+#[test]
+fn atu_add_and_del_entries() {
+    let Some(sim) = VethSim::start("at", &["devid=0"]) else {
+        return;
+    };
+    let atu = |args: &[&str]| {
+        let mut all = vec!["atu", "--devid", "0"];
+        all.extend_from_slice(args);
+        sim.mrmu(&all)
+    };
+
+    let out = stdout_of(&atu(&[
+        "add",
+        "--mac",
+        "00:11:22:33:44:55",
+        "--ports",
+        "1,3",
+        "--static",
+    ]));
+    assert_eq!(
+        out,
+        "loaded fid:0 mac(H):00:11:22:33:44:55 entry_state(H):F portvec(B):0000001010 qpri:0 fpri:0\n"
+    );
+    let out = stdout_of(&atu(&[
+        "add",
+        "--fid",
+        "2",
+        "--mac",
+        "01:00:5E:00:00:01",
+        "--ports",
+        "9",
+        "--qpri",
+        "3",
+    ]));
+    assert_eq!(
+        out,
+        "loaded fid:2 mac(H):01:00:5E:00:00:01 entry_state(H):7 portvec(B):1000000000 qpri:3 fpri:0\n"
+    );
+    // replaces the entry
+    let out = stdout_of(&atu(&["add", "--mac", "00:11:22:33:44:55", "--ports", "4"]));
+    assert!(
+        out.contains("entry_state(H):7 portvec(B):0000010000"),
+        "{out}"
+    );
+
+    let out = atu(&["add", "--mac", "00:11:22:33:44:56", "--ports", "10"]);
+    assert!(!out.status.success());
+    assert!(String::from_utf8_lossy(&out.stderr).contains("no port 10"));
+
+    for _ in 0..2 {
+        let out = stdout_of(&atu(&["del", "--mac", "00:11:22:33:44:55"]));
+        assert_eq!(out, "purged fid:0 mac(H):00:11:22:33:44:55\n");
+    }
+    let out = stdout_of(&atu(&["del", "--fid", "2", "--mac", "01:00:5e:00:00:01"]));
+    assert_eq!(out, "purged fid:2 mac(H):01:00:5E:00:00:01\n");
+}
+
+#[test]
+fn atu_flush_and_move() {
+    let Some(sim) = VethSim::start("fm", &["devid=0"]) else {
+        return;
+    };
+    let atu = |args: &[&str]| {
+        let mut all = vec!["atu", "--devid", "0"];
+        all.extend_from_slice(args);
+        stdout_of(&sim.mrmu(&all))
+    };
+    let read_atu = || stdout_of(&sim.mrmu(&["read-atu", "--devid", "0", "--fid", "0"]));
+
+    atu(&[
+        "add",
+        "--mac",
+        "00:00:00:00:00:0A",
+        "--ports",
+        "2",
+        "--static",
+    ]);
+    atu(&["add", "--mac", "00:00:00:00:00:0B", "--ports", "2"]);
+    atu(&["add", "--mac", "00:00:00:00:00:0C", "--ports", "3"]);
+    atu(&[
+        "add",
+        "--fid",
+        "1",
+        "--mac",
+        "00:00:00:00:00:0D",
+        "--ports",
+        "3",
+    ]);
+
+    let out = atu(&["move", "--from", "2", "--to", "5", "--non-static"]);
+    assert_eq!(out, "moved non-static entries of port 2 to port 5\n");
+    let out = atu(&["flush", "--port", "3", "--fid", "0"]);
+    assert_eq!(out, "flushed entries of port 3 in fid 0\n");
+
+    let out = read_atu();
+    assert!(out.contains("mac(H):00:00:00:00:00:0A entry_state(H):F portvec(B):0000000100"));
+    assert!(out.contains("mac(H):00:00:00:00:00:0B entry_state(H):7 portvec(B):0000100000"));
+    assert!(!out.contains("00:00:00:00:00:0C"), "{out}");
+
+    let out = atu(&["flush", "--non-static"]);
+    assert_eq!(out, "flushed non-static entries\n");
+    let out = read_atu();
+    assert!(out.contains("00:00:00:00:00:0A"), "{out}");
+    assert!(!out.contains("00:00:00:00:00:0B"), "{out}");
+    let out = stdout_of(&sim.mrmu(&["read-atu", "--devid", "0", "--fid", "1"]));
+    assert!(out.is_empty(), "{out}");
+
+    atu(&[
+        "add",
+        "--fid",
+        "1",
+        "--mac",
+        "00:00:00:00:00:0D",
+        "--ports",
+        "3",
+    ]);
+    atu(&["flush", "--fid", "0"]);
+    assert!(read_atu().is_empty());
+    let out = stdout_of(&sim.mrmu(&["read-atu", "--devid", "0", "--fid", "1"]));
+    assert!(out.contains("00:00:00:00:00:0D"), "{out}");
+
+    let out = sim.mrmu(&["atu", "--devid", "0", "move", "--from", "2", "--to", "2"]);
+    assert!(!out.status.success());
+}
+
+#[test]
+fn read_atu_looks_up_and_filters() {
+    let Some(sim) = VethSim::start("rl", &["devid=0"]) else {
+        return;
+    };
+    for (fid, mac, ports, state) in [
+        ("0", "00:00:00:00:00:01", "1", "--static"),
+        ("0", "00:00:00:00:00:02", "2", ""),
+        ("0", "00:00:00:00:00:03", "1,2", ""),
+        ("7", "00:00:00:00:00:02", "3", ""),
+        ("4095", "00:00:00:00:00:00", "4", "--static"),
+    ] {
+        let mut args = vec![
+            "atu", "--devid", "0", "add", "--fid", fid, "--mac", mac, "--ports", ports,
+        ];
+        if !state.is_empty() {
+            args.push(state);
+        }
+        stdout_of(&sim.mrmu(&args));
+    }
+    let read_atu = |args: &[&str]| {
+        let mut all = vec!["read-atu", "--devid", "0"];
+        all.extend_from_slice(args);
+        stdout_of(&sim.mrmu(&all))
+    };
+    let macs = |out: String| -> Vec<String> {
+        out.lines()
+            .map(|line| line.split(" entry_state").next().unwrap().to_string())
+            .collect()
+    };
+
+    assert_eq!(
+        read_atu(&[]),
+        "fid:0 mac(H):00:00:00:00:00:01 entry_state(H):F portvec(B):0000000010 qpri:0 fpri:0\n\
+         fid:0 mac(H):00:00:00:00:00:02 entry_state(H):7 portvec(B):0000000100 qpri:0 fpri:0\n\
+         fid:0 mac(H):00:00:00:00:00:03 entry_state(H):7 portvec(B):0000000110 qpri:0 fpri:0\n"
+    );
+    assert_eq!(
+        macs(read_atu(&["--port", "2", "--state", "7"])),
+        [
+            "fid:0 mac(H):00:00:00:00:00:02",
+            "fid:0 mac(H):00:00:00:00:00:03"
+        ]
+    );
+    assert_eq!(
+        macs(read_atu(&["--state", "0xF"])),
+        ["fid:0 mac(H):00:00:00:00:00:01"]
+    );
+    assert_eq!(
+        macs(read_atu(&[
+            "--dest",
+            "00:50:43:00:00:00",
+            "--mac",
+            "00:00:00:00:00:02"
+        ])),
+        ["fid:0 mac(H):00:00:00:00:00:02"]
+    );
+    assert!(read_atu(&["--mac", "00:00:00:00:00:04"]).is_empty());
+
+    assert_eq!(
+        macs(read_atu(&["--all-fids"])),
+        [
+            "fid:0 mac(H):00:00:00:00:00:01",
+            "fid:0 mac(H):00:00:00:00:00:02",
+            "fid:0 mac(H):00:00:00:00:00:03",
+            "fid:7 mac(H):00:00:00:00:00:02",
+            "fid:4095 mac(H):00:00:00:00:00:00",
+        ]
+    );
+    assert_eq!(
+        macs(read_atu(&["--all-fids", "--mac", "00:00:00:00:00:02"])),
+        [
+            "fid:0 mac(H):00:00:00:00:00:02",
+            "fid:7 mac(H):00:00:00:00:00:02"
+        ]
+    );
+    assert_eq!(
+        macs(read_atu(&["--all-fids", "--mac", "00:00:00:00:00:00"])),
+        ["fid:4095 mac(H):00:00:00:00:00:00"]
+    );
+}
+
+#[test]
+fn atu_watch_reports_changes() {
+    let Some(sim) = VethSim::start("aw", &["devid=0"]) else {
+        return;
+    };
+    let mut watch = Command::new(MRMU)
+        .args(["atu", "--interface", &sim.cli_end, "--devid", "0"])
+        .args(["watch", "--interval", "300ms", "--count", "20"])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut lines = BufReader::new(watch.stdout.take().unwrap()).lines();
+    assert_eq!(lines.next().unwrap().unwrap(), "watching 0 entries");
+    let mut next_event = || {
+        let line = lines.next().unwrap().unwrap();
+        let (stamp, event) = line.split_once(' ').unwrap();
+        assert!(stamp.starts_with('+'), "{line}");
+        event.to_string()
+    };
+    // change the table halfway between two walks
+    let halfway = || std::thread::sleep(std::time::Duration::from_millis(150));
+    let atu = |args: &[&str]| {
+        let mut all = vec!["atu", "--devid", "0"];
+        all.extend_from_slice(args);
+        stdout_of(&sim.mrmu(&all));
+    };
+
+    halfway();
+    atu(&[
+        "add",
+        "--mac",
+        "00:11:22:33:44:55",
+        "--ports",
+        "1",
+        "--static",
+    ]);
+    atu(&["add", "--mac", "00:11:22:33:44:66", "--ports", "2"]);
+    assert_eq!(next_event(), "00:11:22:33:44:55 fid 0 ports 1 static");
+    assert_eq!(next_event(), "00:11:22:33:44:66 fid 0 ports 2");
+    halfway();
+    atu(&["move", "--from", "2", "--to", "5"]);
+    assert_eq!(next_event(), "Moved 00:11:22:33:44:66 fid 0 ports 2 -> 5");
+    halfway();
+    atu(&["del", "--mac", "00:11:22:33:44:55"]);
+    assert_eq!(next_event(), "Deleted 00:11:22:33:44:55 fid 0 ports 1");
+
+    let _ = watch.kill();
+    let _ = watch.wait();
+    // the adds and the walks run side by side without taking each other's replies
+    let mut stderr = String::new();
+    watch
+        .stderr
+        .take()
+        .unwrap()
+        .read_to_string(&mut stderr)
+        .unwrap();
+    assert!(!stderr.contains("walk failed"), "{stderr}");
+}
+
+#[test]
+fn read_atu_batches_get_next() {
+    let Some(sim) = VethSim::start("bg", &["devid=0"]) else {
+        return;
+    };
+    // load 100 static entries to port 1 in one regop run
+    let mut actions = Vec::new();
+    for i in 0..100 {
+        actions.extend([
+            String::from("WRITE:global1.atu_fid,data=0"),
+            String::from("WRITE:global1.atu_mac01,data=0"),
+            String::from("WRITE:global1.atu_mac23,data=0x0001"),
+            format!("WRITE:global1.atu_mac45,data=0x{:04X}", i),
+            String::from("WRITE:global1.atu_data,data=0x002F"),
+            String::from("WRITE:global1.atu_operation,data=0xB000"),
+        ]);
+    }
+    let mut args = vec!["regop", "--devid", "0", "--actions"];
+    args.extend(actions.iter().map(String::as_str));
+    stdout_of(&sim.mrmu(&args));
+
+    let path = std::env::temp_dir().join(format!("mrmu-atu-{}.pcapng", std::process::id()));
+    let out = stdout_of(&sim.mrmu(&[
+        "read-atu",
+        "--devid",
+        "0",
+        "--capture",
+        path.to_str().unwrap(),
+    ]));
+    let frames = capture::read_capture(&std::fs::read(&path).unwrap()).unwrap();
+    let _ = std::fs::remove_file(&path);
+
+    let macs: Vec<&str> = out
+        .lines()
+        .map(|line| {
+            line.split(' ')
+                .nth(1)
+                .unwrap()
+                .trim_start_matches("mac(H):")
+        })
+        .collect();
+    let expect: Vec<String> = (0..100)
+        .map(|i| format!("00:00:00:01:00:{:02X}", i))
+        .collect();
+    assert_eq!(macs, expect);
+
+    // a first GetNext, then 45 per frame
+    let sent = frames
+        .iter()
+        .filter(|frame| frame.direction == Some(Direction::Outbound))
+        .count();
+    assert_eq!(sent, 4);
+}
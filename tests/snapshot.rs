@@ -0,0 +1,154 @@
+//! Register snapshots and their field level differences
+
+use mrmu::reginfo::RegisterDb;
+use mrmu::snapshot::{self, RegisterValue, Snapshot};
+
+fn snapshot(values: &[(u8, u8, u16)]) -> Snapshot {
+    Snapshot {
+        devid: 0,
+        product_number: 0x1520,
+        registers: values
+            .iter()
+            .map(|&(addr, reg, value)| RegisterValue {
+                addr,
+                reg,
+                name: String::new(),
+                value,
+            })
+            .collect(),
+    }
+}
+
+#[test]
+fn diff_decodes_fields() {
+    let db = RegisterDb::builtin();
+    let old = snapshot(&[
+        (3, 0x04, 0x000F),
+        (0x1B, 0x01, 0x0000),
+        (3, 0x13, 0x1234),
+        (0x1C, 0x06, 0x0000),
+    ]);
+    let new = snapshot(&[
+        (3, 0x04, 0x0036),
+        (0x1B, 0x01, 0x0000),
+        (0x1C, 0x05, 0x0080),
+        // device_mapping only shows the entry its pointer selects
+        (0x1C, 0x06, 0x0203),
+    ]);
+
+    let lines: Vec<String> = snapshot::diff(&old, &new, &db)
+        .iter()
+        .map(|change| change.to_string())
+        .collect();
+    assert_eq!(
+        lines,
+        [
+            "port3.port_control0.initial_pri 0 -> 3",
+            "port3.port_control0.egress_floods 3 (all) -> 1 (multicast)",
+            "port3.port_control0.port_state 3 (forwarding) -> 2 (learning)",
+            "port3.0x13 0x1234 -> -",
+            "global2.switch_management - -> 0x0080",
+        ]
+    );
+    assert!(snapshot::diff(&old, &old, &db).is_empty());
+}
+
+#[test]
+fn undescribed_bits_show_the_register() {
+    let db = RegisterDb::builtin();
+    // bit 15 of port_control3 belongs to no field
+    let old = snapshot(&[(1, 0x19, 0x0000)]);
+    let new = snapshot(&[(1, 0x19, 0x8001)]);
+
+    let lines: Vec<String> = snapshot::diff(&old, &new, &db)
+        .iter()
+        .map(|change| change.to_string())
+        .collect();
+    assert_eq!(
+        lines,
+        [
+            "port1.port_control3.use_cfi_yellow 0 -> 1",
+            "port1.port_control3 0x0000 -> 0x8001",
+        ]
+    );
+}
+
+#[test]
+fn snapshot_file_round_trip() {
+    let path = std::env::temp_dir().join(format!("mrmu-snap-{}.json", std::process::id()));
+    let snap = snapshot(&[(0, 0, 0x0E00), (0x1C, 0x1A, 0x12A5)]);
+    snap.save(&path).unwrap();
+    let loaded = Snapshot::load(&path);
+    std::fs::write(&path, "{").unwrap();
+    let broken = Snapshot::load(&path);
+    let _ = std::fs::remove_file(&path);
+
+    assert_eq!(loaded.unwrap(), snap);
+    assert!(broken.is_err());
+}
+
+#[test]
+fn restore_writes_writable_fields_in_order() {
+    let db = RegisterDb::builtin();
+    let saved = snapshot(&[
+        // port_control0, port_status, switch_identifier
+        (2, 0x04, 0x000F),
+        (2, 0x00, 0x0E00),
+        (2, 0x03, 0x1520),
+        // port_control1, global1 switch_control with sw_reset, atu_fid
+        (2, 0x05, 0x0010),
+        (0x1B, 0x04, 0x8000),
+        (0x1B, 0x01, 0x0010),
+        (0x1B, 0x0B, 0x0000),
+    ]);
+    let live = snapshot(&[
+        (2, 0x04, 0x000C),
+        (2, 0x00, 0x0000),
+        (2, 0x03, 0x1521),
+        (2, 0x05, 0x0000),
+        (0x1B, 0x04, 0x0000),
+        (0x1B, 0x01, 0x0000),
+        // busy alone is no reason to write
+        (0x1B, 0x0B, 0x8000),
+    ]);
+
+    let writes = snapshot::restore_writes(&saved, &live, &db);
+    let lines: Vec<String> = writes.iter().map(|w| w.to_string()).collect();
+    assert_eq!(
+        lines,
+        [
+            "WRITE:global1.atu_fid,data=0x0010 was 0x0000",
+            "WRITE:port2.port_control1,data=0x0010 was 0x0000",
+            "WRITE:port2.port_control0,data=0x000F was 0x000C",
+        ]
+    );
+    assert_eq!(writes[0].verify_mask, 0x0FFF);
+    assert!(snapshot::restore_writes(&saved, &saved, &db).is_empty());
+}
+
+#[test]
+fn restore_skips_tables_and_write_only_fields() {
+    let db = RegisterDb::parse(
+        "[[register]]\nname = \"ctl\"\ndevice = \"global2\"\noffset = 0x05\n\
+         fields = [ { name = \"go\", bits = \"15\", access = \"wo\" },\n\
+                    { name = \"mode\", bits = \"3:0\" } ]\n\
+         [[register]]\nname = \"table\"\ndevice = \"global2\"\noffset = 0x06\n\
+         index = [\"pointer\"]\n\
+         fields = [ { name = \"update\", bits = \"15\", access = \"sc\" },\n\
+                    { name = \"pointer\", bits = \"12:8\" },\n\
+                    { name = \"data\", bits = \"4:0\" } ]\n",
+    )
+    .unwrap();
+    let saved = snapshot(&[(0x1C, 0x05, 0x8003), (0x1C, 0x06, 0x0000)]);
+    let live = snapshot(&[(0x1C, 0x05, 0x0001), (0x1C, 0x06, 0x0203)]);
+
+    let lines: Vec<String> = snapshot::restore_writes(&saved, &live, &db)
+        .iter()
+        .map(|w| w.to_string())
+        .collect();
+    assert_eq!(lines, ["WRITE:global2.ctl,data=0x0003 was 0x0001"]);
+
+    // a write only bit read back differently is no reason to write
+    let live = snapshot(&[(0x1C, 0x05, 0x0003), (0x1C, 0x06, 0x0000)]);
+    assert!(snapshot::restore_writes(&saved, &live, &db).is_empty());
+}